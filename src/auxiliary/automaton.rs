@@ -0,0 +1,251 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use randomize::PCG32;
+use winit_input_helper::WinitInputHelper;
+
+use crate::auxiliary::history::{put_u64, take_bytes, take_u64};
+use crate::auxiliary::randomizer::seeded_rng;
+
+/// What a project is about and the keys it adds to the shared controls,
+/// shown both in the terminal menu and in the help overlay.
+#[derive(Clone, Copy, Debug)]
+pub struct Help {
+    pub description: &'static str,
+    /// Each key with what it does
+    pub keys: &'static [(&'static str, &'static str)],
+}
+
+/// Everything the shared runner needs to know about a cellular automaton.
+///
+/// Each project implements this for its grid type and hands it to
+/// `auxiliary::runner::run`, which owns the window and the event loop.
+pub trait Automaton {
+    /// Width and height of the grid in cells.
+    fn size(&self) -> (usize, usize);
+
+    /// Advance the automaton by one generation.
+    fn update(&mut self);
+
+    /// Fill an RGBA frame buffer with the current state.
+    fn draw(&self, screen: &mut [u8]);
+
+    /// Handle a mouse click on a cell. The returned value is passed back
+    /// into `set_line` while the mouse is held down. By default the cell
+    /// flips between empty and 1.
+    fn toggle(&mut self, x: isize, y: isize) -> bool {
+        match grid_pos(x, y, self.size()) {
+            Some((x, y)) => {
+                let alive = self.cell(x, y) == 0;
+                self.set_cell(x, y, alive as u32);
+                alive
+            }
+            None => false,
+        }
+    }
+
+    /// Draw a line of cells while the mouse is dragged, alive or empty.
+    fn set_line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize, alive: bool) {
+        for (x, y) in line_cells((x0, y0), (x1, y1), self.size()) {
+            self.set_cell(x, y, alive as u32);
+        }
+    }
+
+    /// The value of the cell at (x, y) on the grid: whether it's alive, its
+    /// color or its grains. Zero is always the empty cell.
+    fn cell(&self, x: usize, y: usize) -> u32;
+
+    /// Set the cell at (x, y) on the grid to a value like `cell` returns.
+    fn set_cell(&mut self, x: usize, y: usize, value: u32);
+
+    /// The value a selection is filled with.
+    fn fill_value(&self) -> u32 {
+        1
+    }
+
+    /// A random value for a single cell, as `randomize` would choose.
+    fn random_cell(&self, rng: &mut PCG32) -> u32;
+
+    /// Replace the grid with an empty one of `width` by `height` cells.
+    fn set_size(&mut self, width: usize, height: usize);
+
+    /// Change the grid to `width` by `height` cells, keeping the cells both
+    /// sizes have in common and leaving any new ones empty.
+    fn resize(&mut self, width: usize, height: usize) {
+        let (old_width, old_height) = self.size();
+        let kept_width = old_width.min(width);
+        let kept: Vec<u32> = positions(kept_width, old_height.min(height))
+            .map(|(x, y)| self.cell(x, y))
+            .collect();
+        self.set_size(width, height);
+        for (i, &value) in kept.iter().enumerate() {
+            self.set_cell(i % kept_width, i / kept_width, value);
+        }
+    }
+
+    /// The whole state of the grid as bytes, for the undo history. By
+    /// default this is what `save_cells` writes.
+    fn save_state(&self) -> Vec<u8> {
+        save_cells(self)
+    }
+
+    /// Go back to a state made by `save_state`, resizing the grid to the size
+    /// it had then.
+    fn load_state(&mut self, mut state: &[u8]) {
+        load_cells(self, &mut state);
+    }
+
+    /// Reset every cell to its empty state.
+    fn clear(&mut self);
+
+    /// How many cells are alive, or whatever else the project counts as
+    /// named by `population_name`, for the HUD.
+    fn population(&self) -> u64;
+
+    /// What `population` counts.
+    fn population_name(&self) -> &'static str {
+        "population"
+    }
+
+    /// The rule being run, for the HUD.
+    fn rule_name(&self) -> String;
+
+    /// A window title naming the rule, for projects whose title changes
+    /// along with it. Otherwise the title given to `launch` is kept.
+    fn title(&self) -> Option<String> {
+        None
+    }
+
+    /// The rule in B/S notation, for grids whose rules can be written in it.
+    fn rulestring(&self) -> Option<String> {
+        None
+    }
+
+    /// Switch to the rule given in B/S notation, as pattern files do.
+    fn set_rulestring(&mut self, _rule: &str) -> Result<(), String> {
+        Err("this automaton doesn't use rulestrings".to_string())
+    }
+
+    /// Fill the grid with a random starting state, made only from `seed`
+    /// so it can be recreated. By default every cell gets a `random_cell`.
+    fn randomize(&mut self, seed: u64) {
+        let mut rng = seeded_rng(seed);
+        let (width, height) = self.size();
+        for (x, y) in positions(width, height) {
+            let value = self.random_cell(&mut rng);
+            self.set_cell(x, y, value);
+        }
+    }
+
+    /// Whether the automaton is randomized before it is first shown, from
+    /// the seed given on the command line if there is one. The rest are
+    /// randomized when a seed is given, unless they start from a preset.
+    fn starts_random(&self) -> bool {
+        false
+    }
+
+    /// Whether the grid starts from a preset that a seed given on the
+    /// command line shouldn't replace.
+    fn has_preset(&self) -> bool {
+        false
+    }
+
+    /// The description and keys of the project, which should list every key
+    /// `handle_keys` responds to.
+    fn help(&self) -> &'static Help;
+
+    /// Hook for keys that only make sense for this project. Called once per
+    /// input update, before the automaton is stepped.
+    fn handle_keys(&mut self, _input: &WinitInputHelper) {}
+}
+
+/// Every cell of a `width` by `height` grid, a row at a time.
+fn positions(width: usize, height: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+}
+
+/// The size, cells and rulestring of a grid as bytes, which is all
+/// `save_state` keeps unless a project has more state to add after it. The
+/// rule is kept because loading a pattern can change it.
+pub fn save_cells<A: Automaton + ?Sized>(automaton: &A) -> Vec<u8> {
+    let (width, height) = automaton.size();
+    let cells: Vec<u32> = positions(width, height).map(|(x, y)| automaton.cell(x, y)).collect();
+    // Most grids fit in a byte a cell, so only the low bytes in use are kept
+    let largest = cells.iter().copied().max().unwrap_or(0);
+    let planes = (4 - largest.leading_zeros() as usize / 8).max(1);
+    let mut state = Vec::with_capacity(17 + planes * cells.len());
+    put_u64(&mut state, width as u64);
+    put_u64(&mut state, height as u64);
+    state.push(planes as u8);
+    for plane in 0..planes {
+        state.extend(cells.iter().map(|c| c.to_le_bytes()[plane]));
+    }
+    let rule = automaton.rulestring().unwrap_or_default();
+    put_u64(&mut state, rule.len() as u64);
+    state.extend_from_slice(rule.as_bytes());
+    state
+}
+
+/// Read the cells and rule written by `save_cells` off the front of `state`,
+/// resizing the grid to the size it had then.
+pub fn load_cells<A: Automaton + ?Sized>(automaton: &mut A, state: &mut &[u8]) {
+    let width = take_u64(state) as usize;
+    let height = take_u64(state) as usize;
+    if (width, height) != automaton.size() {
+        automaton.resize(width, height);
+    }
+    let planes = take_bytes(state, 1)[0];
+    let planes: Vec<&[u8]> = (0..planes).map(|_| take_bytes(state, width * height)).collect();
+    for (i, (x, y)) in positions(width, height).enumerate() {
+        let value = planes.iter().rev().fold(0, |value, plane| value << 8 | plane[i] as u32);
+        automaton.set_cell(x, y, value);
+    }
+    let length = take_u64(state) as usize;
+    let rule = take_bytes(state, length);
+    if !rule.is_empty() {
+        let rule = std::str::from_utf8(rule).expect("saved rulestrings are text");
+        automaton.set_rulestring(rule).expect("saved rulestrings can be set again");
+    }
+}
+
+/// The cell at (x, y) if it is on a grid of `size`.
+pub fn grid_pos(x: isize, y: isize, (width, height): (usize, usize)) -> Option<(usize, usize)> {
+    match (usize::try_from(x), usize::try_from(y)) {
+        (Ok(x), Ok(y)) if x < width && y < height => Some((x, y)),
+        _ => None,
+    }
+}
+
+/// The cells of a line on a grid of `size`, for drawing with the mouse.
+pub fn line_cells(
+    (x0, y0): (isize, isize),
+    (x1, y1): (isize, isize),
+    size: (usize, usize),
+) -> impl Iterator<Item = (usize, usize)> {
+    // probably should do sutherland-hodgeman if this were more serious.
+    // instead just clamp the start pos, and draw until moving towards the
+    // end pos takes us out of bounds.
+    let x0 = x0.max(0).min(size.0 as isize);
+    let y0 = y0.max(0).min(size.1 as isize);
+    line_drawing::Bresenham::new((x0, y0), (x1, y1)).map_while(move |(x, y)| grid_pos(x, y, size))
+}
+
+/// Copy a `width` by `height` grid into one of `new_width` by `new_height`,
+/// keeping the cells of the top left corner they share and filling the rest
+/// with `empty`.
+pub fn resize_cells<T: Clone>(
+    cells: &[T],
+    width: usize,
+    height: usize,
+    new_width: usize,
+    new_height: usize,
+    empty: T,
+) -> Vec<T> {
+    debug_assert_eq!(cells.len(), width * height);
+    let mut resized = vec![empty; new_width * new_height];
+    let kept = width.min(new_width);
+    for y in 0..height.min(new_height) {
+        resized[y * new_width..y * new_width + kept].clone_from_slice(&cells[y * width..y * width + kept]);
+    }
+    resized
+}
@@ -0,0 +1,155 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+// Boundary conditions for 2D grids
+//
+// Each boundary says which cell sits just beyond an edge of the grid. Most of
+// them glue the edges together into a surface, the rest surround the grid with
+// a border whose cells never change.
+
+use winit::event::VirtualKeyCode;
+use winit_input_helper::WinitInputHelper;
+
+/// How a grid treats the cells just beyond its edges.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Boundary {
+    /// Opposite edges are glued together
+    #[default]
+    Torus,
+    /// Everything beyond the edge is dead
+    Dead,
+    /// Everything beyond the edge is alive
+    Live,
+    /// The edge reflects, so the cell beyond it is the edge cell itself
+    Mirror,
+    /// Left and right are glued together, top and bottom are glued with a
+    /// half twist
+    Klein,
+    /// Left and right are glued together, top and bottom are dead
+    Cylinder,
+}
+
+/// What is found at a position next to the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighbor {
+    /// A cell of the grid
+    Cell(usize),
+    /// The reflection of a cell of the grid, which can be read but shouldn't
+    /// be written to since the cell itself is already there
+    Image(usize),
+    /// A border cell that never changes
+    Fixed(bool),
+}
+
+impl Neighbor {
+    /// Whether the neighbor is alive, looking up grid cells with `alive`.
+    pub fn is_alive(self, alive: impl Fn(usize) -> bool) -> bool {
+        match self {
+            Neighbor::Cell(i) | Neighbor::Image(i) => alive(i),
+            Neighbor::Fixed(state) => state,
+        }
+    }
+}
+
+// Offsets of the eight cells around a cell
+const MOORE: [(isize, isize);8] = [
+    (-1, -1), (0, -1), (1, -1),
+    (-1, 0), (1, 0),
+    (-1, 1), (0, 1), (1, 1),
+];
+
+pub const BOUNDARY_NAMES: &str = "torus, dead, live, mirror, klein or cylinder";
+
+impl Boundary {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "torus" => Ok(Boundary::Torus),
+            "dead" => Ok(Boundary::Dead),
+            "live" => Ok(Boundary::Live),
+            "mirror" => Ok(Boundary::Mirror),
+            "klein" => Ok(Boundary::Klein),
+            "cylinder" => Ok(Boundary::Cylinder),
+            _ => Err(format!("unknown boundary '{}', expected {}", name, BOUNDARY_NAMES)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Boundary::Torus => "torus",
+            Boundary::Dead => "dead",
+            Boundary::Live => "live",
+            Boundary::Mirror => "mirror",
+            Boundary::Klein => "klein",
+            Boundary::Cylinder => "cylinder",
+        }
+    }
+
+    /// The boundary after this one, for cycling through them with a key.
+    pub fn next(self) -> Self {
+        match self {
+            Boundary::Torus => Boundary::Dead,
+            Boundary::Dead => Boundary::Live,
+            Boundary::Live => Boundary::Mirror,
+            Boundary::Mirror => Boundary::Klein,
+            Boundary::Klein => Boundary::Cylinder,
+            Boundary::Cylinder => Boundary::Torus,
+        }
+    }
+
+    /// Switch to the next boundary when B is pressed.
+    pub fn handle_keys(&mut self, input: &WinitInputHelper) {
+        if input.key_pressed(VirtualKeyCode::B) {
+            *self = self.next();
+            println!("{} boundary", self.name());
+        }
+    }
+
+    /// Find the cell at (x, y), which may be up to one cell beyond any edge of
+    /// a width by height grid.
+    pub fn locate(self, x: isize, y: isize, width: usize, height: usize) -> Neighbor {
+        let (w, h) = (width as isize, height as isize);
+        let x_inside = (0..w).contains(&x);
+        let y_inside = (0..h).contains(&y);
+        let index = |x: isize, y: isize| (x + y * w) as usize;
+        if x_inside && y_inside {
+            return Neighbor::Cell(index(x, y));
+        }
+        match self {
+            Boundary::Torus => Neighbor::Cell(index(x.rem_euclid(w), y.rem_euclid(h))),
+            Boundary::Dead => Neighbor::Fixed(false),
+            Boundary::Live => Neighbor::Fixed(true),
+            Boundary::Mirror => Neighbor::Image(index(x.clamp(0, w - 1), y.clamp(0, h - 1))),
+            Boundary::Klein => {
+                let x = if y_inside { x } else { w - 1 - x };
+                Neighbor::Cell(index(x.rem_euclid(w), y.rem_euclid(h)))
+            }
+            Boundary::Cylinder if y_inside => Neighbor::Cell(index(x.rem_euclid(w), y)),
+            Boundary::Cylinder => Neighbor::Fixed(false),
+        }
+    }
+
+    /// Count the live cells among the eight around (x, y).
+    pub fn count_neibs(
+        self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        alive: impl Fn(usize) -> bool,
+    ) -> usize {
+        // Away from the edges every boundary is the same
+        if x > 0 && y > 0 && x + 1 < width && y + 1 < height {
+            return MOORE
+                .iter()
+                .filter(|(dx, dy)| alive((x as isize + dx) as usize + (y as isize + dy) as usize * width))
+                .count();
+        }
+        MOORE
+            .iter()
+            .filter(|(dx, dy)| {
+                self.locate(x as isize + dx, y as isize + dy, width, height)
+                    .is_alive(&alive)
+            })
+            .count()
+    }
+}
@@ -0,0 +1,133 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+// Zooming out further than this would only show a few pixels
+const MIN_ZOOM: i32 = -6;
+// and zooming in further would only show a few cells
+const MAX_ZOOM: i32 = 6;
+
+// Color of the view outside the world
+const BACKGROUND: [u8;4] = [0x20, 0x20, 0x20, 0xff];
+
+/// Where the window looks at the world and how closely.
+///
+/// The view is a fixed size frame of pixels. A cell covers 2^zoom pixels
+/// across, so zooming in makes cells bigger and zooming out below 0 shows
+/// one cell out of every block of cells under each pixel.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    /// Size of the view in pixels
+    pub width: u32,
+    pub height: u32,
+    world_width: u32,
+    world_height: u32,
+    // Position in the world, in cells, shown at the center of the view
+    center_x: f64,
+    center_y: f64,
+    zoom: i32,
+}
+
+impl Camera {
+    /// A camera showing the top left of the world one pixel per cell, through
+    /// a view as big as the world or the maximum view size, whichever is less.
+    pub fn new(world_width: u32, world_height: u32, max_width: u32, max_height: u32) -> Self {
+        let width = world_width.min(max_width);
+        let height = world_height.min(max_height);
+        Self {
+            width,
+            height,
+            world_width,
+            world_height,
+            center_x: width as f64 / 2.0,
+            center_y: height as f64 / 2.0,
+            zoom: 0,
+        }
+    }
+
+    // Pixels across a cell
+    fn scale(&self) -> f64 {
+        2f64.powi(self.zoom)
+    }
+
+    /// Move the view by a number of pixels. The center of the view always
+    /// stays on the world so it can't get lost.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        let scale = self.scale();
+        self.center_x = (self.center_x + dx / scale).clamp(0.0, self.world_width as f64);
+        self.center_y = (self.center_y + dy / scale).clamp(0.0, self.world_height as f64);
+    }
+
+    /// Zoom in by `steps` doubling steps (out when negative), keeping the
+    /// point at pixel (x, y) of the view in place.
+    pub fn zoom_at(&mut self, steps: i32, x: f64, y: f64) {
+        let zoom = (self.zoom + steps).clamp(MIN_ZOOM, MAX_ZOOM);
+        let (fixed_x, fixed_y) = self.world_pos(x, y);
+        self.zoom = zoom;
+        let scale = self.scale();
+        self.center_x = fixed_x - (x - self.width as f64 / 2.0) / scale;
+        self.center_y = fixed_y - (y - self.height as f64 / 2.0) / scale;
+        self.pan(0.0, 0.0);
+    }
+
+    /// Center the world and zoom as far in as possible while all of it fits.
+    pub fn fit(&mut self) {
+        let fit_x = self.width as f64 / self.world_width as f64;
+        let fit_y = self.height as f64 / self.world_height as f64;
+        self.zoom = (fit_x.min(fit_y).log2().floor() as i32).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center_x = self.world_width as f64 / 2.0;
+        self.center_y = self.world_height as f64 / 2.0;
+    }
+
+    // The position in the world, in fractions of cells, at a point of the view
+    fn world_pos(&self, x: f64, y: f64) -> (f64, f64) {
+        let scale = self.scale();
+        (
+            self.center_x + (x - self.width as f64 / 2.0) / scale,
+            self.center_y + (y - self.height as f64 / 2.0) / scale,
+        )
+    }
+
+    /// The point of the view, in fractions of pixels, showing a position in
+    /// the world.
+    pub fn to_view(&self, x: f64, y: f64) -> (f64, f64) {
+        let scale = self.scale();
+        (
+            (x - self.center_x) * scale + self.width as f64 / 2.0,
+            (y - self.center_y) * scale + self.height as f64 / 2.0,
+        )
+    }
+
+    /// The cell under a pixel of the view, which may be outside the world.
+    pub fn to_world(&self, x: isize, y: isize) -> (isize, isize) {
+        let (x, y) = self.world_pos(x as f64 + 0.5, y as f64 + 0.5);
+        (x.floor() as isize, y.floor() as isize)
+    }
+
+    /// Draw the view from an RGBA frame of the whole world into an RGBA frame
+    /// the size of the view.
+    pub fn render(&self, world: &[u8], view: &mut [u8]) {
+        debug_assert_eq!(world.len(), (4 * self.world_width * self.world_height) as usize);
+        debug_assert_eq!(view.len(), (4 * self.width * self.height) as usize);
+        let world_width = self.world_width as usize;
+        // Every row of the view samples the same columns of the world
+        let columns: Vec<Option<usize>> = (0..self.width as isize)
+            .map(|x| {
+                let (x, _) = self.to_world(x, 0);
+                usize::try_from(x).ok().filter(|&x| x < world_width)
+            })
+            .collect();
+        for (y, row) in view.chunks_exact_mut(4 * self.width as usize).enumerate() {
+            let (_, world_y) = self.to_world(0, y as isize);
+            let world_row = usize::try_from(world_y)
+                .ok()
+                .filter(|&y| y < self.world_height as usize)
+                .map(|y| &world[4 * y * world_width..4 * (y + 1) * world_width]);
+            for (pix, column) in row.chunks_exact_mut(4).zip(columns.iter()) {
+                match (world_row, column) {
+                    (Some(world_row), Some(x)) => pix.copy_from_slice(&world_row[4 * x..4 * x + 4]),
+                    _ => pix.copy_from_slice(&BACKGROUND),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+// A tiny bitmap font for drawing text into a frame
+//
+// Each glyph is 3 pixels wide and 5 tall, stored as five rows of three bits
+// with the leftmost pixel in the highest bit. Lowercase letters are drawn as
+// uppercase and anything without a glyph as a question mark.
+
+pub const GLYPH_WIDTH: u32 = 3;
+pub const GLYPH_HEIGHT: u32 = 5;
+// Distance from the start of one character to the next and between lines
+pub const ADVANCE: u32 = GLYPH_WIDTH + 1;
+pub const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 1;
+
+fn glyph(c: char) -> [u8;5] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '*' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '[' => [0b011, 0b010, 0b010, 0b010, 0b011],
+        ']' => [0b110, 0b010, 0b010, 0b010, 0b110],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+/// Width in pixels of a line of text drawn at `scale`.
+pub fn text_width(text: &str, scale: u32) -> u32 {
+    match text.chars().count() as u32 {
+        0 => 0,
+        n => (n * ADVANCE - 1) * scale,
+    }
+}
+
+/// Draw a line of text into an RGBA frame `width` pixels wide with its top
+/// left corner at `pos`, each pixel of the font becoming a `scale` by `scale`
+/// square. Whatever falls outside the frame is cut off.
+pub fn draw_text(frame: &mut [u8], width: u32, pos: (u32, u32), text: &str, scale: u32, color: [u8;4]) {
+    let height = frame.len() as u32 / (4 * width);
+    for (i, c) in text.chars().enumerate() {
+        let left = pos.0 + i as u32 * ADVANCE * scale;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits >> (GLYPH_WIDTH - 1 - col) & 1 == 0 {
+                    continue;
+                }
+                let x0 = left + col * scale;
+                let y0 = pos.1 + row as u32 * scale;
+                for y in y0..(y0 + scale).min(height) {
+                    for x in x0..(x0 + scale).min(width) {
+                        let i = 4 * (x + y * width) as usize;
+                        frame[i..i + 4].copy_from_slice(&color);
+                    }
+                }
+            }
+        }
+    }
+}
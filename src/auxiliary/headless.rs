@@ -0,0 +1,82 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::auxiliary::automaton::Automaton;
+use crate::auxiliary::options::Options;
+use crate::auxiliary::randomizer::Seeder;
+use crate::auxiliary::recorder::Recorder;
+use crate::auxiliary::screenshot::{save_png, upscale};
+
+
+/// Step the automaton `options.steps` times without a window and write the
+/// final frame to `options.output`. If the output is a `.gif` every
+/// `options.frame_skip`th generation is recorded into an animation instead.
+pub fn run_headless<A: Automaton>(mut automaton: A, title: &str, options: &Options, seeder: Seeder) -> io::Result<()> {
+    if seeder.used() {
+        println!("Running {} for {} steps with seed {}", title, options.steps, seeder.current());
+    } else {
+        println!("Running {} for {} steps", title, options.steps);
+    }
+    let (width, height) = automaton.size();
+    let (width, height) = (width as u32, height as u32);
+    let mut frame = vec![0; (4 * width * height) as usize];
+    if has_extension(&options.output, "gif") {
+        let mut recorder = Recorder::new(
+            &options.output,
+            width,
+            height,
+            options.scale.unwrap_or(1),
+            options.frame_skip,
+            options.max_frames,
+        )?;
+        automaton.draw(&mut frame);
+        recorder.capture(&frame)?;
+        for _ in 0..options.steps {
+            automaton.update();
+            automaton.draw(&mut frame);
+            if !recorder.capture(&frame)? {
+                break;
+            }
+        }
+        println!("{} frames written to {}", recorder.frames_written(), options.output.display());
+        return Ok(());
+    }
+    for _ in 0..options.steps {
+        automaton.update();
+    }
+    automaton.draw(&mut frame);
+    save_frame(&options.output, &frame, width, height, options.scale.unwrap_or(1))?;
+    println!("Final frame written to {}", options.output.display());
+    Ok(())
+}
+
+/// Whether a path ends in `.extension`, in any case.
+pub fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Save a frame as PNG if the path ends in `.png`, otherwise as PPM.
+pub fn save_frame(path: &Path, frame: &[u8], width: u32, height: u32, scale: u32) -> io::Result<()> {
+    if has_extension(path, "png") {
+        save_png(path, frame, width, height, scale)
+    } else {
+        let scale = scale.max(1);
+        write_ppm(path, &upscale(frame, width, height, scale), width * scale, height * scale)
+    }
+}
+
+/// Write an RGBA frame buffer as a binary PPM image, dropping the alpha channel.
+pub fn write_ppm(path: &Path, frame: &[u8], width: u32, height: u32) -> io::Result<()> {
+    debug_assert_eq!(frame.len(), (4 * width * height) as usize);
+    let mut out = BufWriter::new(File::create(path)?);
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    for pix in frame.chunks_exact(4) {
+        out.write_all(&pix[..3])?;
+    }
+    out.flush()
+}
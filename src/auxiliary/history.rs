@@ -0,0 +1,153 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::collections::VecDeque;
+
+// Saved grid states for undo, redo and stepping back through generations
+//
+// An automaton saves its whole state as bytes, starting with its size and
+// followed by each kind of value for every cell in turn, so neighboring bytes
+// tend to repeat. The stacks keep them compressed with PackBits run length
+// encoding.
+
+// Most states each stack keeps
+const MAX_STATES: usize = 256;
+// Most compressed bytes each stack keeps
+const MAX_BYTES: usize = 64 << 20;
+
+/// Append a number to a saved state.
+pub fn put_u64(state: &mut Vec<u8>, n: u64) {
+    state.extend_from_slice(&n.to_le_bytes());
+}
+
+/// Read a number from the front of a saved state and move past it.
+pub fn take_u64(state: &mut &[u8]) -> u64 {
+    let bytes = take_bytes(state, 8);
+    u64::from_le_bytes(bytes.try_into().unwrap())
+}
+
+/// Read `n` bytes from the front of a saved state and move past them.
+pub fn take_bytes<'a>(state: &mut &'a [u8], n: usize) -> &'a [u8] {
+    let (bytes, rest) = state.split_at(n);
+    *state = rest;
+    bytes
+}
+
+// PackBits: a header byte below 128 is followed by that many plus one bytes
+// to copy, any other header by a single byte repeated 257 minus header times
+fn compress(bytes: &[u8]) -> Vec<u8> {
+    let mut packed = Vec::with_capacity(bytes.len() / 8);
+    let mut i = 0;
+    while i < bytes.len() {
+        let run = bytes[i..].iter().take(128).take_while(|&&b| b == bytes[i]).count();
+        if run > 1 {
+            packed.push((257 - run) as u8);
+            packed.push(bytes[i]);
+            i += run;
+        } else {
+            // Copy bytes up to the next run of at least three
+            let start = i;
+            while i < bytes.len() && i - start < 128 {
+                if i + 2 < bytes.len() && bytes[i] == bytes[i + 1] && bytes[i] == bytes[i + 2] {
+                    break;
+                }
+                i += 1;
+            }
+            packed.push((i - start - 1) as u8);
+            packed.extend_from_slice(&bytes[start..i]);
+        }
+    }
+    packed
+}
+
+fn decompress(mut packed: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    while let Some((&header, rest)) = packed.split_first() {
+        if header < 128 {
+            let (literal, rest) = rest.split_at(header as usize + 1);
+            bytes.extend_from_slice(literal);
+            packed = rest;
+        } else {
+            bytes.extend(std::iter::repeat_n(rest[0], 257 - header as usize));
+            packed = &rest[1..];
+        }
+    }
+    bytes
+}
+
+/// A bounded stack of compressed states, each with the generation it was
+/// saved at, that forgets the oldest ones first.
+#[derive(Clone, Debug, Default)]
+pub struct StateStack {
+    states: VecDeque<(u64, Vec<u8>)>,
+    bytes: usize,
+}
+
+impl StateStack {
+    pub fn push(&mut self, generation: u64, state: &[u8]) {
+        let packed = compress(state);
+        if packed.len() > MAX_BYTES {
+            return;
+        }
+        self.bytes += packed.len();
+        self.states.push_back((generation, packed));
+        while self.states.len() > MAX_STATES || self.bytes > MAX_BYTES {
+            if let Some((_, oldest)) = self.states.pop_front() {
+                self.bytes -= oldest.len();
+            }
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<(u64, Vec<u8>)> {
+        let (generation, packed) = self.states.pop_back()?;
+        self.bytes -= packed.len();
+        Some((generation, decompress(&packed)))
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+        self.bytes = 0;
+    }
+}
+
+/// Edits that can be undone and redone, and the states before recent
+/// generations so the simulation can be stepped backwards.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    undo: StateStack,
+    redo: StateStack,
+    generations: StateStack,
+}
+
+impl History {
+    /// Remember the state from before an edit.
+    pub fn edited(&mut self, generation: u64, before: &[u8]) {
+        self.undo.push(generation, before);
+        self.redo.clear();
+    }
+
+    /// The state before the last edit, if any, keeping `current` for redo.
+    pub fn undo(&mut self, generation: u64, current: &[u8]) -> Option<(u64, Vec<u8>)> {
+        let previous = self.undo.pop()?;
+        self.redo.push(generation, current);
+        Some(previous)
+    }
+
+    /// The state the last undo went back from, if any, keeping `current` for
+    /// undo.
+    pub fn redo(&mut self, generation: u64, current: &[u8]) -> Option<(u64, Vec<u8>)> {
+        let next = self.redo.pop()?;
+        self.undo.push(generation, current);
+        Some(next)
+    }
+
+    /// Remember the state from before stepping.
+    pub fn stepped(&mut self, generation: u64, before: &[u8]) {
+        self.generations.push(generation, before);
+    }
+
+    /// The state before the most recent steps that are still kept.
+    pub fn step_back(&mut self) -> Option<(u64, Vec<u8>)> {
+        self.generations.pop()
+    }
+}
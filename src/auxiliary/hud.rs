@@ -0,0 +1,152 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::time::{Duration, Instant};
+
+use crate::auxiliary::font::{draw_text, text_width, ADVANCE, LINE_HEIGHT};
+
+// How often the measured speed is brought up to date
+const SPEED_INTERVAL: Duration = Duration::from_millis(500);
+
+const TEXT_COLOR: [u8;4] = [0xff, 0xff, 0xff, 0xff];
+
+// Characters between columns of text
+const COLUMN_GAP: usize = 2;
+// Narrowest column text is split into before giving up on fitting it
+const MIN_COLUMN_WIDTH: usize = 24;
+
+// Big views are shown smaller so the text is scaled up to match
+fn text_scale(width: u32) -> u32 {
+    (width / 480).max(1)
+}
+
+/// How many characters fit on a line drawn over a view `width` pixels wide.
+pub fn columns(width: u32) -> usize {
+    // One pixel of margin on each side
+    ((width / text_scale(width)).saturating_sub(1) / ADVANCE).max(1) as usize
+}
+
+/// How many lines fit on a view `width` by `height` pixels.
+pub fn rows(width: u32, height: u32) -> usize {
+    // One pixel of margin at the bottom
+    ((height / text_scale(width)).saturating_sub(1) / LINE_HEIGHT).max(1) as usize
+}
+
+/// Break text into lines of at most `columns` characters, between words
+/// where possible.
+pub fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.len() > columns {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        // Words longer than a line are split wherever they run out of room
+        while line.chars().count() + word.len() > columns {
+            let room = columns - line.chars().count();
+            line.extend(word.drain(..room));
+            lines.push(std::mem::take(&mut line));
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Wrap paragraphs into lines that fit a view `width` by `height` pixels,
+/// putting them side by side in columns when there are too many to fit one
+/// above the other. Whatever still doesn't fit is cut off.
+pub fn wrap_columns(paragraphs: &[String], width: u32, height: u32) -> Vec<String> {
+    let (columns, rows) = (columns(width), rows(width, height));
+    let mut count = 1;
+    loop {
+        let column_width = ((columns + COLUMN_GAP) / count).saturating_sub(COLUMN_GAP).max(1);
+        let lines: Vec<String> = paragraphs.iter().flat_map(|p| wrap(p, column_width)).collect();
+        let narrowest = (columns + COLUMN_GAP) / (count + 1) < MIN_COLUMN_WIDTH + COLUMN_GAP;
+        if lines.len() <= count * rows || narrowest {
+            if count == 1 {
+                return lines;
+            }
+            let chunks: Vec<&[String]> = lines.chunks(rows).collect();
+            return (0..rows.min(lines.len()))
+                .map(|row| {
+                    let mut line = String::new();
+                    for chunk in &chunks {
+                        let text = chunk.get(row).map_or("", String::as_str);
+                        line.push_str(&format!("{:w$}", text, w = column_width + COLUMN_GAP));
+                    }
+                    line.trim_end().to_string()
+                })
+                .collect();
+        }
+        count += 1;
+    }
+}
+
+/// Lines of text drawn over the corner of the view, along with the speed of
+/// the simulation they report.
+#[derive(Clone, Debug)]
+pub struct Hud {
+    pub visible: bool,
+    // generations counted since `since`
+    counted: u32,
+    since: Instant,
+    speed: f64,
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            counted: 0,
+            since: Instant::now(),
+            speed: 0.0,
+        }
+    }
+}
+
+impl Hud {
+    /// Count a generation towards the measured speed.
+    pub fn count_generation(&mut self) {
+        self.counted += 1;
+    }
+
+    /// Generations per second, averaged over the last half second or so.
+    pub fn speed(&mut self) -> f64 {
+        let elapsed = self.since.elapsed();
+        if elapsed >= SPEED_INTERVAL {
+            self.speed = self.counted as f64 / elapsed.as_secs_f64();
+            self.counted = 0;
+            self.since = Instant::now();
+        }
+        self.speed
+    }
+
+    /// Draw `lines` in the top left corner of an RGBA frame `width` pixels
+    /// wide, over a darkened box so they can be read on any background.
+    pub fn draw(&self, frame: &mut [u8], width: u32, lines: &[String]) {
+        let height = frame.len() as u32 / (4 * width);
+        let scale = text_scale(width);
+        let margin = scale;
+        let box_width = lines.iter().map(|l| text_width(l, scale)).max().unwrap_or(0) + 2 * margin;
+        let box_height = lines.len() as u32 * LINE_HEIGHT * scale + margin;
+        for y in 0..box_height.min(height) {
+            for x in 0..box_width.min(width) {
+                let i = 4 * (x + y * width) as usize;
+                for channel in &mut frame[i..i + 3] {
+                    *channel /= 3;
+                }
+            }
+        }
+        for (row, line) in lines.iter().enumerate() {
+            let y = margin + row as u32 * LINE_HEIGHT * scale;
+            draw_text(frame, width, (margin, y), line, scale, TEXT_COLOR);
+        }
+    }
+}
@@ -0,0 +1,392 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::collections::HashMap;
+
+use crate::auxiliary::selection::Pattern;
+
+// Golly's Macrocell format (https://conwaylife.com/wiki/Macrocell)
+//
+// After the `[M2]` line and comments like `#R B3/S23`, every line is a node
+// of a quadtree, numbered from 1 in order. A node at level k is a square
+// 2^k cells across. Two state patterns give their 8 by 8 squares at level 3
+// as rows of `.` and `*` ending in `$`, and patterns with more states give
+// the four states of their 2 by 2 squares at level 1 as `1 nw ne sw se`.
+// Bigger nodes are lines like `k nw ne sw se` giving the numbers of their
+// four quarters, with 0 for an empty quarter of any size. The last node is
+// the whole pattern.
+//
+// Macrocell patterns can be far bigger than any grid, so only the part of
+// them that fits is ever taken out of the tree.
+
+// Largest node level, so positions fit in 64 bits
+const MAX_LEVEL: u32 = 62;
+
+#[derive(Clone, Debug)]
+enum Node {
+    // Cells of a level 3 or level 1 square that aren't empty
+    Leaf { level: u32, cells: Vec<(u64, u64, u32)> },
+    Branch { level: u32, quarters: [usize;4] },
+}
+
+impl Node {
+    fn level(&self) -> u32 {
+        match *self {
+            Node::Leaf { level, .. } | Node::Branch { level, .. } => level,
+        }
+    }
+}
+
+// Rows of a level 3 square
+fn parse_leaf(line: &str) -> Result<Node, String> {
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    for c in line.chars() {
+        match c {
+            '.' => x += 1,
+            '*' => {
+                cells.push((x, y, 1));
+                x += 1;
+            }
+            '$' => {
+                x = 0;
+                y += 1;
+            }
+            _ => return Err(format!("unexpected '{}' in Macrocell leaf", c)),
+        }
+        if x > 8 || y > 8 || (y == 8 && x > 0) {
+            return Err(format!("Macrocell leaf '{}' is bigger than 8 by 8", line));
+        }
+    }
+    Ok(Node::Leaf { level: 3, cells })
+}
+
+// A line of numbers, either four states or four earlier nodes
+fn parse_branch(line: &str, nodes: &[Node]) -> Result<Node, String> {
+    let numbers = line
+        .split_whitespace()
+        .map(|n| n.parse::<u64>().map_err(|_| format!("invalid Macrocell node '{}'", line)))
+        .collect::<Result<Vec<u64>, String>>()?;
+    let [level, nw, ne, sw, se]: [u64;5] = numbers
+        .try_into()
+        .map_err(|_| format!("Macrocell node '{}' needs five numbers", line))?;
+    let level = level as u32;
+    if level == 1 {
+        let cells = [(0, 0, nw), (1, 0, ne), (0, 1, sw), (1, 1, se)]
+            .into_iter()
+            .filter(|&(_, _, state)| state != 0)
+            .map(|(x, y, state)| (x, y, state as u32))
+            .collect();
+        return Ok(Node::Leaf { level, cells });
+    }
+    if !(2..=MAX_LEVEL).contains(&level) {
+        return Err(format!("Macrocell node level {} isn't from 1 to {}", level, MAX_LEVEL));
+    }
+    let mut quarters = [0;4];
+    for (quarter, &n) in quarters.iter_mut().zip(&[nw, ne, sw, se]) {
+        let n = n as usize;
+        // Node 0 is empty, and only earlier nodes can be quarters
+        if n > nodes.len() || (n > 0 && nodes[n - 1].level() != level - 1) {
+            return Err(format!("Macrocell node '{}' has an invalid quarter {}", line, n));
+        }
+        *quarter = n;
+    }
+    Ok(Node::Branch { level, quarters })
+}
+
+/// A Macrocell quadtree as read from a file, with the rule it gives.
+#[derive(Clone, Debug)]
+pub struct Macrocell {
+    // Node n is nodes[n - 1]
+    nodes: Vec<Node>,
+    // Smallest rectangle around the cells of each node that aren't empty,
+    // as left, top, right and bottom with the last two excluded
+    bounds: Vec<Option<[u64;4]>>,
+    pub rule: Option<String>,
+}
+
+impl Macrocell {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut tree = Self {
+            nodes: Vec::new(),
+            bounds: Vec::new(),
+            rule: None,
+        };
+        let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+        match lines.next() {
+            Some(header) if header.starts_with("[M2]") => (),
+            _ => return Err("Macrocell files start with [M2]".to_string()),
+        }
+        for line in lines {
+            if let Some(rule) = line.strip_prefix("#R") {
+                tree.rule = Some(rule.trim().to_string());
+                continue;
+            }
+            if line.starts_with('#') {
+                continue;
+            }
+            let node = if line.starts_with(['.', '*', '$']) {
+                parse_leaf(line)?
+            } else {
+                parse_branch(line, &tree.nodes)?
+            };
+            tree.bounds.push(tree.node_bounds(&node));
+            tree.nodes.push(node);
+        }
+        Ok(tree)
+    }
+
+    fn bounds_of(&self, n: usize) -> Option<[u64;4]> {
+        match n {
+            0 => None,
+            n => self.bounds[n - 1],
+        }
+    }
+
+    fn node_bounds(&self, node: &Node) -> Option<[u64;4]> {
+        let mut found: Option<[u64;4]> = None;
+        let mut include = |[left, top, right, bottom]: [u64;4]| {
+            found = Some(match found {
+                Some([l, t, r, b]) => [l.min(left), t.min(top), r.max(right), b.max(bottom)],
+                None => [left, top, right, bottom],
+            });
+        };
+        match node {
+            Node::Leaf { cells, .. } => {
+                for &(x, y, _) in cells {
+                    include([x, y, x + 1, y + 1]);
+                }
+            }
+            Node::Branch { level, quarters } => {
+                let half = 1 << (level - 1);
+                for (i, &quarter) in quarters.iter().enumerate() {
+                    let (dx, dy) = (half * (i as u64 % 2), half * (i as u64 / 2));
+                    if let Some([l, t, r, b]) = self.bounds_of(quarter) {
+                        include([l + dx, t + dy, r + dx, b + dy]);
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    // Copy the cells of node n, whose top left corner is at (x, y), that are
+    // inside the pattern placed with its top left corner at (left, top)
+    fn extract(&self, n: usize, (x, y): (u64, u64), (left, top): (u64, u64), pattern: &mut Pattern) {
+        let Some([l, t, r, b]) = self.bounds_of(n) else {
+            return;
+        };
+        let (width, height) = (pattern.width as u64, pattern.height as u64);
+        if x + r <= left || y + b <= top || x + l >= left + width || y + t >= top + height {
+            return;
+        }
+        match &self.nodes[n - 1] {
+            Node::Leaf { cells, .. } => {
+                for &(cx, cy, state) in cells {
+                    let (px, py) = (x + cx, y + cy);
+                    if (left..left + width).contains(&px) && (top..top + height).contains(&py) {
+                        pattern.cells[(px - left + (py - top) * width) as usize] = state;
+                    }
+                }
+            }
+            Node::Branch { level, quarters } => {
+                let half = 1 << (level - 1);
+                for (i, &quarter) in quarters.iter().enumerate() {
+                    let corner = (x + half * (i as u64 % 2), y + half * (i as u64 / 2));
+                    self.extract(quarter, corner, (left, top), pattern);
+                }
+            }
+        }
+    }
+
+    /// The cells of the pattern, cut down to at most `max_width` by
+    /// `max_height` around the middle of the cells that aren't empty.
+    pub fn to_pattern(&self, max_width: usize, max_height: usize) -> Pattern {
+        let root = self.nodes.len();
+        let Some([l, t, r, b]) = self.bounds_of(root) else {
+            return Pattern { width: 0, height: 0, cells: Vec::new() };
+        };
+        let width = (r - l).min(max_width as u64);
+        let height = (b - t).min(max_height as u64);
+        let left = l + (r - l - width) / 2;
+        let top = t + (b - t - height) / 2;
+        let mut pattern = Pattern {
+            width: width as usize,
+            height: height as usize,
+            cells: vec![0; (width * height) as usize],
+        };
+        self.extract(root, (0, 0), (left, top), &mut pattern);
+        pattern
+    }
+}
+
+// Builds the lines of a Macrocell file, sharing nodes that are the same
+struct Writer<'a> {
+    pattern: &'a Pattern,
+    two_states: bool,
+    lines: Vec<String>,
+    numbers: HashMap<String, usize>,
+}
+
+impl Writer<'_> {
+    fn value(&self, x: u64, y: u64) -> u32 {
+        let (width, height) = (self.pattern.width as u64, self.pattern.height as u64);
+        if x < width && y < height {
+            self.pattern.cells[(x + y * width) as usize]
+        } else {
+            0
+        }
+    }
+
+    // The number of the node with its top left corner at (x, y), after
+    // writing it and its quarters if they haven't been yet
+    fn node(&mut self, x: u64, y: u64, level: u32) -> usize {
+        if x >= self.pattern.width as u64 || y >= self.pattern.height as u64 {
+            return 0;
+        }
+        let line = if self.two_states && level == 3 {
+            let rows: Vec<String> = (0..8)
+                .map(|dy| {
+                    let row: String = (0..8).map(|dx| if self.value(x + dx, y + dy) == 0 { '.' } else { '*' }).collect();
+                    row.trim_end_matches('.').to_string()
+                })
+                .collect();
+            let used = rows.iter().rposition(|row| !row.is_empty()).map_or(0, |i| i + 1);
+            if used == 0 {
+                return 0;
+            }
+            rows[..used].iter().map(|row| format!("{}$", row)).collect()
+        } else if level == 1 {
+            let states = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| self.value(x + dx, y + dy).min(255));
+            if states == [0;4] {
+                return 0;
+            }
+            format!("1 {} {} {} {}", states[0], states[1], states[2], states[3])
+        } else {
+            let half = 1 << (level - 1);
+            let quarters = [(0, 0), (half, 0), (0, half), (half, half)].map(|(dx, dy)| self.node(x + dx, y + dy, level - 1));
+            if quarters == [0;4] {
+                return 0;
+            }
+            format!("{} {} {} {} {}", level, quarters[0], quarters[1], quarters[2], quarters[3])
+        };
+        if let Some(&n) = self.numbers.get(&line) {
+            return n;
+        }
+        self.lines.push(line.clone());
+        self.numbers.insert(line, self.lines.len());
+        self.lines.len()
+    }
+}
+
+/// Write a pattern as Macrocell, with a rule line if given. Values above
+/// 255 can't be written and are kept at 255.
+pub fn to_macrocell(pattern: &Pattern, rule: Option<&str>) -> String {
+    let two_states = pattern.cells.iter().all(|&v| v <= 1);
+    // Even small patterns get a node above the 8 by 8 squares, as Golly writes
+    let size = pattern.width.max(pattern.height).max(16).next_power_of_two();
+    let mut writer = Writer {
+        pattern,
+        two_states,
+        lines: Vec::new(),
+        numbers: HashMap::new(),
+    };
+    writer.node(0, 0, size.trailing_zeros());
+    let mut text = String::from("[M2] (pixel_art_mine)\n");
+    if let Some(rule) = rule {
+        text.push_str(&format!("#R {}\n", rule));
+    }
+    for line in writer.lines {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glider() -> Pattern {
+        Pattern {
+            width: 3,
+            height: 3,
+            cells: vec![0, 1, 0, 0, 0, 1, 1, 1, 1],
+        }
+    }
+
+    #[test]
+    fn two_state_round_trip() {
+        let text = to_macrocell(&glider(), Some("B3/S23"));
+        let tree = Macrocell::parse(&text).unwrap();
+        assert_eq!(tree.rule.as_deref(), Some("B3/S23"));
+        assert_eq!(tree.to_pattern(100, 100), glider());
+    }
+
+    #[test]
+    fn multi_state_round_trip() {
+        let pattern = Pattern {
+            width: 5,
+            height: 3,
+            cells: vec![2, 0, 0, 0, 7, 0, 1, 0, 0, 0, 0, 0, 0, 3, 255],
+        };
+        let text = to_macrocell(&pattern, None);
+        assert!(text.lines().any(|line| line.starts_with("1 ")));
+        let tree = Macrocell::parse(&text).unwrap();
+        assert_eq!(tree.rule, None);
+        assert_eq!(tree.to_pattern(100, 100), pattern);
+    }
+
+    #[test]
+    fn round_trip_far_from_the_corner() {
+        let mut cells = vec![0; 40 * 30];
+        cells[17 + 9 * 40] = 1;
+        cells[39 + 29 * 40] = 1;
+        let pattern = Pattern { width: 40, height: 30, cells };
+        let tree = Macrocell::parse(&to_macrocell(&pattern, None)).unwrap();
+        assert_eq!(tree.to_pattern(100, 100), pattern.crop(17, 9, 23, 21));
+    }
+
+    #[test]
+    fn golly_leaf_and_level_4() {
+        let text = "[M2] (golly 4.0)\n#R B3/S23\n.*$..*$***$\n4 0 1 0 0\n";
+        let tree = Macrocell::parse(text).unwrap();
+        assert_eq!(tree.rule.as_deref(), Some("B3/S23"));
+        assert_eq!(tree.to_pattern(100, 100), glider());
+    }
+
+    #[test]
+    fn crops_around_the_middle() {
+        let pattern = Pattern {
+            width: 10,
+            height: 10,
+            cells: (1..=100).collect(),
+        };
+        let tree = Macrocell::parse(&to_macrocell(&pattern, None)).unwrap();
+        assert_eq!(tree.to_pattern(4, 6), pattern.crop(3, 2, 4, 6));
+    }
+
+    #[test]
+    fn empty_pattern() {
+        let tree = Macrocell::parse("[M2]\n").unwrap();
+        assert_eq!(tree.to_pattern(10, 10).cells, Vec::<u32>::new());
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        // No header
+        assert!(Macrocell::parse("4 0 0 0 0\n").is_err());
+        // Levels out of range
+        assert!(Macrocell::parse("[M2]\n0 0 0 0 0\n").is_err());
+        assert!(Macrocell::parse("[M2]\n63 0 0 0 0\n").is_err());
+        // A quarter that comes later
+        assert!(Macrocell::parse("[M2]\n1 1 0 0 0\n2 3 0 0 0\n2 1 0 0 0\n").is_err());
+        // A quarter of the wrong level
+        assert!(Macrocell::parse("[M2]\n1 1 0 0 0\n3 1 0 0 0\n").is_err());
+        // Leaves bigger than 8 by 8 and lines that aren't nodes
+        assert!(Macrocell::parse("[M2]\n*********$\n").is_err());
+        assert!(Macrocell::parse("[M2]\n$$$$$$$$*$\n").is_err());
+        assert!(Macrocell::parse("[M2]\n2 0 0 0\n").is_err());
+        assert!(Macrocell::parse("[M2]\n2 a 0 0 0\n").is_err());
+    }
+}
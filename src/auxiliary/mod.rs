@@ -1,2 +1,19 @@
 pub mod window;
-pub mod randomizer;
\ No newline at end of file
+pub mod randomizer;
+pub mod automaton;
+pub mod runner;
+pub mod options;
+pub mod headless;
+pub mod screenshot;
+pub mod recorder;
+pub mod rulestring;
+pub mod boundary;
+pub mod camera;
+pub mod font;
+pub mod hud;
+pub mod speed;
+pub mod history;
+pub mod selection;
+pub mod patterns;
+pub mod macrocell;
+pub mod prompt;
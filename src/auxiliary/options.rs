@@ -0,0 +1,168 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use crate::auxiliary::boundary::Boundary;
+use crate::auxiliary::window::{SCREEN_WIDTH, SCREEN_HEIGHT};
+
+// how many generations a headless run simulates when --steps isn't given
+const DEFAULT_STEPS: usize = 1000;
+
+// longest GIF recording in frames when --max-frames isn't given
+const DEFAULT_MAX_FRAMES: usize = 500;
+
+// largest --width and --height, which keeps the frame of the world in memory
+pub const MAX_GRID_SIZE: usize = 8192;
+
+// largest --scale, so upscaled images of the largest grid still fit in u32
+// sizes and a sensible amount of memory
+const MAX_SCALE: u32 = 16;
+
+/// Settings shared by every project, read from the command line.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Step the automaton without opening a window
+    pub headless: bool,
+    /// Number of generations to simulate in headless mode
+    pub steps: usize,
+    /// Where headless mode writes the final frame, as PNG or PPM depending
+    /// on the extension
+    pub output: PathBuf,
+    /// Integer upscale for saved images. Screenshots default to the window's
+    /// scale and headless output to 1.
+    pub scale: Option<u32>,
+    /// Keep only every Nth generation when recording an animation
+    pub frame_skip: usize,
+    /// Stop recording an animation after this many frames
+    pub max_frames: usize,
+    /// What 2D grids find beyond their edges
+    pub boundary: Boundary,
+    /// Seed for the first random state
+    pub seed: Option<u64>,
+    /// Size of the grid in cells
+    pub width: usize,
+    pub height: usize,
+    /// Resize the grid with the window, keeping the size of a cell, instead
+    /// of stretching the same grid over the window
+    pub fit_window: bool,
+    /// Target generations per second instead of a number per frame
+    pub rate: Option<f64>,
+    /// Pattern file to start from, in any format `read_pattern` knows
+    pub pattern: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            headless: false,
+            steps: DEFAULT_STEPS,
+            output: PathBuf::from("frame.png"),
+            scale: None,
+            frame_skip: 1,
+            max_frames: DEFAULT_MAX_FRAMES,
+            boundary: Boundary::default(),
+            seed: None,
+            width: SCREEN_WIDTH as usize,
+            height: SCREEN_HEIGHT as usize,
+            fit_window: false,
+            rate: None,
+            pattern: None,
+        }
+    }
+}
+
+// The value following `flag`, which can't be another flag, so that a
+// missing value isn't taken from the next option
+fn next_value<I: Iterator<Item = String>>(flag: &str, args: &mut I) -> Result<String, String> {
+    args.next()
+        .filter(|value| !value.starts_with("--"))
+        .ok_or_else(|| format!("{} needs a value", flag))
+}
+
+impl Options {
+    /// Apply a single option, taking its value from `args`. Returns false if
+    /// `arg` isn't one of the shared options.
+    ///
+    /// Recognizes `--headless`, `--steps N`, `--output PATH`, `--scale N`,
+    /// `--frame-skip N`, `--max-frames N`, `--boundary NAME`, `--seed N`,
+    /// `--width N`, `--height N`, `--fit-window`, `--rate N` and
+    /// `--pattern PATH`.
+    pub fn parse_flag<I: Iterator<Item = String>>(&mut self, arg: &str, args: &mut I) -> Result<bool, String> {
+        match arg {
+            "--headless" => self.headless = true,
+            "--fit-window" => self.fit_window = true,
+            "--steps" => {
+                let value = next_value("--steps", args)?;
+                self.steps = value
+                    .parse()
+                    .map_err(|_| format!("invalid step count: {}", value))?;
+            }
+            "--output" => {
+                let value = next_value("--output", args)?;
+                self.output = PathBuf::from(value);
+            }
+            "--scale" => {
+                let value = next_value("--scale", args)?;
+                let scale = value
+                    .parse()
+                    .map_err(|_| format!("invalid scale: {}", value))?;
+                if !(1..=MAX_SCALE).contains(&scale) {
+                    return Err(format!("scale must be from 1 to {}", MAX_SCALE));
+                }
+                self.scale = Some(scale);
+            }
+            "--frame-skip" => {
+                let value = next_value("--frame-skip", args)?;
+                self.frame_skip = value
+                    .parse()
+                    .map_err(|_| format!("invalid frame skip: {}", value))?;
+            }
+            "--max-frames" => {
+                let value = next_value("--max-frames", args)?;
+                self.max_frames = value
+                    .parse()
+                    .map_err(|_| format!("invalid frame count: {}", value))?;
+            }
+            "--boundary" => {
+                let value = next_value("--boundary", args)?;
+                self.boundary = Boundary::from_name(&value)?;
+            }
+            "--seed" => {
+                let value = next_value("--seed", args)?;
+                let seed = value
+                    .parse()
+                    .map_err(|_| format!("invalid seed: {}", value))?;
+                self.seed = Some(seed);
+            }
+            "--width" | "--height" => {
+                let value = next_value(arg, args)?;
+                let size = value
+                    .parse()
+                    .ok()
+                    .filter(|size| (1..=MAX_GRID_SIZE).contains(size))
+                    .ok_or_else(|| format!("{} must be from 1 to {}", &arg[2..], MAX_GRID_SIZE))?;
+                if arg == "--width" {
+                    self.width = size;
+                } else {
+                    self.height = size;
+                }
+            }
+            "--rate" => {
+                let value = next_value("--rate", args)?;
+                let rate = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|rate| rate.is_finite() && *rate > 0.0)
+                    .ok_or_else(|| format!("invalid rate: {}", value))?;
+                self.rate = Some(rate);
+            }
+            "--pattern" => {
+                let value = next_value("--pattern", args)?;
+                self.pattern = Some(PathBuf::from(value));
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
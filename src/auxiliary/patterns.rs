@@ -0,0 +1,607 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::auxiliary::automaton::Automaton;
+use crate::auxiliary::headless::has_extension;
+use crate::auxiliary::macrocell::{to_macrocell, Macrocell};
+use crate::auxiliary::options::MAX_GRID_SIZE;
+use crate::auxiliary::rulestring::{parse_rule, rule_to_string, CONWAY_RULE};
+use crate::auxiliary::selection::Pattern;
+
+// Pattern files as collected on LifeWiki and used by Golly
+//
+// RLE (https://conwaylife.com/wiki/Run_Length_Encoded) starts with a header
+// like `x = 3, y = 3, rule = B3/S23` and gives each row as runs of `b` for
+// dead and `o` for live cells, ending rows with `$` and the pattern with `!`.
+// Patterns with more states write them as `.` for 0 and `A` to `X` for 1 to
+// 24, with a prefix from `p` to `y` for each further 24.
+//
+// Plaintext (https://conwaylife.com/wiki/Plaintext) has one line per row
+// with `.` for dead and `O` for live cells, after comment lines starting
+// with `!`.
+//
+// Life 1.06 (https://conwaylife.com/wiki/Life_1.06) lists the coordinates of
+// live cells one per line after a `#Life 1.06` line. Life 1.05
+// (https://conwaylife.com/wiki/Life_1.05) has blocks of rows like plaintext
+// with `*` for live cells, each starting at the position given by a
+// `#P x y` line, and the rule as `#N` for Conway's Life or `#R 23/3` with
+// survival first. Both are kept in `.lif` or `.life` files, so files are
+// written as Life 1.05 when they end in `.lif` and Life 1.06 when they end
+// in `.life`.
+//
+// Macrocell is in the macrocell module.
+
+// Widest block of a Life 1.05 file
+const LIFE_105_LINE_LENGTH: usize = 80;
+
+// Longest line RLE writers are supposed to produce
+const RLE_LINE_LENGTH: usize = 70;
+
+// Highest state an RLE file can hold
+const MAX_RLE_STATE: u32 = 255;
+
+/// A pattern read from a file, with the rule the file asks for if any.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternFile {
+    pub pattern: Pattern,
+    pub rule: Option<String>,
+}
+
+fn too_big() -> String {
+    format!("pattern is bigger than {} cells across", MAX_GRID_SIZE)
+}
+
+// Live cells found while reading, along with the extent of the pattern
+#[derive(Default)]
+struct Cells {
+    cells: Vec<(usize, usize, u32)>,
+    width: usize,
+    height: usize,
+}
+
+impl Cells {
+    // `count` cells in a row with the same value, starting at (x, y)
+    fn run(&mut self, x: usize, y: usize, count: usize, value: u32) -> Result<(), String> {
+        let end = x.checked_add(count).filter(|&end| end <= MAX_GRID_SIZE).ok_or_else(too_big)?;
+        if y >= MAX_GRID_SIZE {
+            return Err(too_big());
+        }
+        if value != 0 {
+            self.cells.extend((x..end).map(|x| (x, y, value)));
+        }
+        self.width = self.width.max(end);
+        self.height = self.height.max(y + 1);
+        Ok(())
+    }
+
+    // A pattern at least `width` by `height`
+    fn into_pattern(self, width: usize, height: usize) -> Pattern {
+        let width = self.width.max(width);
+        let height = self.height.max(height);
+        let mut cells = vec![0; width * height];
+        for (x, y, value) in self.cells {
+            cells[x + y * width] = value;
+        }
+        Pattern { width, height, cells }
+    }
+}
+
+// The rule from a header or comment, leaving off any bounded grid suffix
+// like `:T100,100` since the grid has its own size
+fn header_rule(rule: &str) -> String {
+    rule.split(':').next().unwrap_or_default().trim().to_string()
+}
+
+/// Read a pattern in RLE. The size the header gives is only kept as far as
+/// it fits in `max_size`, as room around the cells.
+pub fn parse_rle(text: &str, max_size: (usize, usize)) -> Result<PatternFile, String> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty()).peekable();
+    let mut rule = None;
+    while let Some(comment) = lines.next_if(|l| l.starts_with('#')) {
+        // Old files give the rule in a comment
+        if let Some(r) = comment.strip_prefix("#r") {
+            rule = Some(header_rule(r));
+        }
+    }
+    let (mut width, mut height) = (0, 0);
+    if let Some(header) = lines.next_if(|l| l.starts_with('x')) {
+        // The rule comes last and may have commas of its own
+        let (sizes, header_rule_item) = match header.find("rule") {
+            Some(i) => (&header[..i], Some(&header[i..])),
+            None => (header, None),
+        };
+        let items = sizes.split(',').map(str::trim).filter(|item| !item.is_empty());
+        for item in items.chain(header_rule_item) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid RLE header '{}'", header))?;
+            let size = || match value.trim().parse::<usize>() {
+                Ok(size) if size > MAX_GRID_SIZE => Err(too_big()),
+                Ok(size) => Ok(size),
+                Err(_) => Err(format!("invalid size '{}'", value.trim())),
+            };
+            match key.trim() {
+                "x" => width = size()?,
+                "y" => height = size()?,
+                "rule" => rule = Some(header_rule(value)),
+                _ => (),
+            }
+        }
+    }
+    let mut cells = Cells::default();
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut count = None;
+    let mut chars = lines.flat_map(str::chars).peekable();
+    while let Some(c) = chars.next() {
+        let value = match c {
+            '0'..='9' => {
+                let digit = c.to_digit(10).unwrap() as usize;
+                count = Some(count.unwrap_or(0usize).saturating_mul(10).saturating_add(digit));
+                continue;
+            }
+            '!' => break,
+            '$' => {
+                y = y
+                    .checked_add(count.take().unwrap_or(1))
+                    .filter(|&y| y <= MAX_GRID_SIZE)
+                    .ok_or_else(too_big)?;
+                x = 0;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            'b' | '.' => 0,
+            'A'..='X' => c as u32 - 'A' as u32 + 1,
+            'p'..='y' if chars.peek().is_some_and(|next| ('A'..='X').contains(next)) => {
+                let next = chars.next().unwrap();
+                (c as u32 - 'p' as u32 + 1) * 24 + next as u32 - 'A' as u32 + 1
+            }
+            // Two state files may mark live cells with any other letter
+            c if c.is_ascii_lowercase() => 1,
+            _ => return Err(format!("unexpected '{}' in RLE", c)),
+        };
+        let run = count.take().unwrap_or(1);
+        cells.run(x, y, run, value)?;
+        x += run;
+    }
+    Ok(PatternFile {
+        pattern: cells.into_pattern(width.min(max_size.0), height.min(max_size.1)),
+        rule,
+    })
+}
+
+// The letters for a state in RLE
+fn rle_state(value: u32, two_states: bool) -> String {
+    match value.min(MAX_RLE_STATE) {
+        0 if two_states => "b".to_string(),
+        0 => ".".to_string(),
+        1 if two_states => "o".to_string(),
+        n if n <= 24 => char::from(b'A' + (n - 1) as u8).to_string(),
+        n => {
+            let prefix = char::from(b'p' + ((n - 25) / 24) as u8);
+            format!("{}{}", prefix, char::from(b'A' + ((n - 25) % 24) as u8))
+        }
+    }
+}
+
+/// Write a pattern as RLE, with a rule line if given. Values above 255
+/// can't be written and are kept at 255.
+pub fn to_rle(pattern: &Pattern, rule: Option<&str>) -> String {
+    let mut text = format!("x = {}, y = {}", pattern.width, pattern.height);
+    if let Some(rule) = rule {
+        text.push_str(&format!(", rule = {}", rule));
+    }
+    text.push('\n');
+    let two_states = pattern.cells.iter().all(|&v| v <= 1);
+    // Runs of (count, tag), leaving out dead cells at the end of each row and
+    // empty rows at the end
+    let mut items: Vec<(usize, String)> = Vec::new();
+    let mut rows_ended = 0;
+    for row in pattern.cells.chunks(pattern.width.max(1)) {
+        let used = row.iter().rposition(|&v| v != 0).map_or(0, |i| i + 1);
+        if used > 0 && rows_ended > 0 {
+            items.push((rows_ended, "$".to_string()));
+            rows_ended = 0;
+        }
+        let mut x = 0;
+        while x < used {
+            let run = row[x..used].iter().take_while(|&&v| v == row[x]).count();
+            items.push((run, rle_state(row[x], two_states)));
+            x += run;
+        }
+        rows_ended += 1;
+    }
+    items.push((1, "!".to_string()));
+    let mut line = String::new();
+    for (count, tag) in items {
+        let item = match count {
+            1 => tag,
+            n => format!("{}{}", n, tag),
+        };
+        if line.len() + item.len() > RLE_LINE_LENGTH {
+            text.push_str(&line);
+            text.push('\n');
+            line.clear();
+        }
+        line.push_str(&item);
+    }
+    text.push_str(&line);
+    text.push('\n');
+    text
+}
+
+/// Read a pattern in plaintext.
+pub fn parse_cells(text: &str) -> Result<PatternFile, String> {
+    let mut cells = Cells::default();
+    let rows = text.lines().filter(|l| !l.starts_with('!'));
+    for (y, row) in rows.enumerate() {
+        for (x, c) in row.trim_end().chars().enumerate() {
+            cells.run(x, y, 1, !matches!(c, '.' | ' ') as u32)?;
+        }
+    }
+    // Blank lines at the end are only there to end the file
+    let pattern = cells.into_pattern(0, 0);
+    Ok(PatternFile { pattern, rule: None })
+}
+
+/// Write a pattern as plaintext, which only tells whether cells are empty.
+pub fn to_cells(pattern: &Pattern, name: &str) -> String {
+    let mut text = format!("!Name: {}\n", name);
+    for row in pattern.cells.chunks(pattern.width.max(1)) {
+        let used = row.iter().rposition(|&v| v != 0).map_or(0, |i| i + 1);
+        text.extend(row[..used].iter().map(|&v| if v == 0 { '.' } else { 'O' }));
+        text.push('\n');
+    }
+    text
+}
+
+// A pattern from the positions of its live cells, which may be anywhere
+fn from_points(points: &[(i64, i64)]) -> Result<Pattern, String> {
+    let left = points.iter().map(|p| p.0).min().unwrap_or(0);
+    let top = points.iter().map(|p| p.1).min().unwrap_or(0);
+    let mut cells = Cells::default();
+    for &(x, y) in points {
+        let (x, y) = (x.abs_diff(left), y.abs_diff(top));
+        if x >= MAX_GRID_SIZE as u64 || y >= MAX_GRID_SIZE as u64 {
+            return Err(too_big());
+        }
+        cells.run(x as usize, y as usize, 1, 1)?;
+    }
+    Ok(cells.into_pattern(0, 0))
+}
+
+// Positions of the live cells with (0, 0) in the middle of the pattern
+fn to_points(pattern: &Pattern) -> impl Iterator<Item = (i64, i64)> + '_ {
+    let (mid_x, mid_y) = (pattern.width as i64 / 2, pattern.height as i64 / 2);
+    pattern.cells.iter().enumerate().filter(|(_, &v)| v != 0).map(move |(i, _)| {
+        ((i % pattern.width) as i64 - mid_x, (i / pattern.width) as i64 - mid_y)
+    })
+}
+
+/// Read a pattern in Life 1.06.
+pub fn parse_life_106(text: &str) -> Result<PatternFile, String> {
+    let mut points = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let point = line
+            .split_once(char::is_whitespace)
+            .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+            .ok_or_else(|| format!("invalid Life 1.06 cell '{}'", line))?;
+        points.push(point);
+    }
+    Ok(PatternFile {
+        pattern: from_points(&points)?,
+        rule: None,
+    })
+}
+
+/// Write a pattern as Life 1.06, which only tells whether cells are empty
+/// and has no rule.
+pub fn to_life_106(pattern: &Pattern) -> String {
+    let mut text = String::from("#Life 1.06\n");
+    for (x, y) in to_points(pattern) {
+        text.push_str(&format!("{} {}\n", x, y));
+    }
+    text
+}
+
+/// Read a pattern in Life 1.05.
+pub fn parse_life_105(text: &str) -> Result<PatternFile, String> {
+    let mut points = Vec::new();
+    let mut rule = None;
+    // Where the next row of the block starts
+    let (mut left, mut y) = (0i64, 0i64);
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(position) = line.strip_prefix("#P") {
+            let mut numbers = position.split_whitespace().map(str::parse::<i64>);
+            match (numbers.next(), numbers.next()) {
+                (Some(Ok(x)), Some(Ok(top))) => (left, y) = (x, top),
+                _ => return Err(format!("invalid Life 1.05 block '{}'", line)),
+            }
+        } else if line.starts_with("#N") {
+            rule = Some(CONWAY_RULE.to_string());
+        } else if let Some(r) = line.strip_prefix("#R") {
+            rule = Some(r.trim().to_string());
+        } else if !line.starts_with('#') {
+            for (dx, c) in line.chars().enumerate() {
+                match c {
+                    '.' => (),
+                    '*' | 'O' | 'o' => points.push((left.checked_add(dx as i64).ok_or_else(too_big)?, y)),
+                    _ => return Err(format!("unexpected '{}' in Life 1.05", c)),
+                }
+            }
+            y = y.checked_add(1).ok_or_else(too_big)?;
+        }
+    }
+    Ok(PatternFile {
+        pattern: from_points(&points)?,
+        rule,
+    })
+}
+
+/// Write a pattern as Life 1.05, which only tells whether cells are empty,
+/// with a rule line if given.
+pub fn to_life_105(pattern: &Pattern, rule: Option<&str>) -> String {
+    let mut text = String::from("#Life 1.05\n");
+    // Rules that aren't rulestrings can't be written
+    if let Some(Ok((birth, survive))) = rule.map(parse_rule) {
+        if rule_to_string(birth, survive) == CONWAY_RULE {
+            text.push_str("#N\n");
+        } else {
+            let digits = |arr: [bool;9]| -> String {
+                (0..9).filter(|&n| arr[n]).map(|n| char::from(b'0' + n as u8)).collect()
+            };
+            text.push_str(&format!("#R {}/{}\n", digits(survive), digits(birth)));
+        }
+    }
+    let (mid_x, mid_y) = (pattern.width / 2, pattern.height / 2);
+    // Blocks of whole columns, narrow enough for the lines to stay short
+    for block_left in (0..pattern.width).step_by(LIFE_105_LINE_LENGTH) {
+        let block_width = LIFE_105_LINE_LENGTH.min(pattern.width - block_left);
+        let rows: Vec<String> = pattern
+            .cells
+            .chunks(pattern.width)
+            .map(|row| {
+                let row: String = row[block_left..block_left + block_width]
+                    .iter()
+                    .map(|&v| if v == 0 { '.' } else { '*' })
+                    .collect();
+                row.trim_end_matches('.').to_string()
+            })
+            .collect();
+        let (Some(first), Some(last)) = (
+            rows.iter().position(|row| !row.is_empty()),
+            rows.iter().rposition(|row| !row.is_empty()),
+        ) else {
+            continue;
+        };
+        let (x, y) = (block_left as i64 - mid_x as i64, first as i64 - mid_y as i64);
+        text.push_str(&format!("#P {} {}\n", x, y));
+        for row in &rows[first..=last] {
+            // Empty rows still need a character to count as rows
+            text.push_str(if row.is_empty() { "." } else { row });
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// The kinds of pattern file that can be read and written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+    Macrocell,
+}
+
+impl Format {
+    /// The format a file name asks for: plaintext for `.cells` or `.txt`,
+    /// Life 1.05 for `.lif`, Life 1.06 for `.life`, Macrocell for `.mc` and
+    /// RLE for anything else.
+    pub fn from_path(path: &Path) -> Self {
+        let formats = [
+            ("cells", Format::Plaintext),
+            ("txt", Format::Plaintext),
+            ("lif", Format::Life105),
+            ("life", Format::Life106),
+            ("mc", Format::Macrocell),
+        ];
+        formats
+            .into_iter()
+            .find(|(extension, _)| has_extension(path, extension))
+            .map_or(Format::Rle, |(_, format)| format)
+    }
+
+    // The format of a file, from the first line if it tells and otherwise
+    // from its name
+    fn detect(text: &str, path: &Path) -> Self {
+        let first = text.lines().next().unwrap_or_default().trim();
+        if first.starts_with("#Life 1.05") {
+            Format::Life105
+        } else if first.starts_with("#Life 1.06") {
+            Format::Life106
+        } else if first.starts_with("[M2]") {
+            Format::Macrocell
+        } else if first.starts_with('!') {
+            Format::Plaintext
+        } else {
+            Self::from_path(path)
+        }
+    }
+}
+
+/// Read a pattern file in any of the formats, telling them apart by their
+/// first line or else by their names. Macrocell patterns can be too big to
+/// hold, so they're cut down to at most `max_size` around the middle of
+/// their live cells, and RLE headers only add room up to `max_size`.
+pub fn read_pattern(path: &Path, max_size: (usize, usize)) -> io::Result<PatternFile> {
+    let text = fs::read_to_string(path)?;
+    let parsed = match Format::detect(&text, path) {
+        Format::Rle => parse_rle(&text, max_size),
+        Format::Plaintext => parse_cells(&text),
+        Format::Life105 => parse_life_105(&text),
+        Format::Life106 => parse_life_106(&text),
+        Format::Macrocell => Macrocell::parse(&text).map(|tree| PatternFile {
+            pattern: tree.to_pattern(max_size.0, max_size.1),
+            rule: tree.rule,
+        }),
+    };
+    parsed.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write a pattern file in the format its name asks for.
+pub fn write_pattern(path: &Path, pattern: &Pattern, rule: Option<&str>) -> io::Result<()> {
+    let text = match Format::from_path(path) {
+        Format::Rle => to_rle(pattern, rule),
+        Format::Plaintext => {
+            let name = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+            to_cells(pattern, &name)
+        }
+        Format::Life105 => to_life_105(pattern, rule),
+        Format::Life106 => to_life_106(pattern),
+        Format::Macrocell => to_macrocell(pattern, rule),
+    };
+    fs::write(path, text)
+}
+
+/// Clear the grid and put a pattern in the middle of it, cutting off the
+/// edges of patterns too big to fit. A rule the grid can't follow is
+/// reported and the grid keeps its own.
+pub fn place<A: Automaton>(automaton: &mut A, file: &PatternFile) {
+    if let Some(rule) = &file.rule {
+        match automaton.set_rulestring(rule) {
+            Ok(()) => println!("following rule {} from the pattern", automaton.rule_name()),
+            Err(e) => println!("keeping rule {}: {}", automaton.rule_name(), e),
+        }
+    }
+    let (width, height) = automaton.size();
+    let pattern = &file.pattern;
+    let cropped = pattern.crop(
+        pattern.width.saturating_sub(width) / 2,
+        pattern.height.saturating_sub(height) / 2,
+        pattern.width.min(width),
+        pattern.height.min(height),
+    );
+    automaton.clear();
+    cropped.paste(automaton, (width - cropped.width) / 2, (height - cropped.height) / 2);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRID: (usize, usize) = (MAX_GRID_SIZE, MAX_GRID_SIZE);
+
+    fn glider() -> Pattern {
+        Pattern {
+            width: 3,
+            height: 3,
+            cells: vec![0, 1, 0, 0, 0, 1, 1, 1, 1],
+        }
+    }
+
+    #[test]
+    fn rle_round_trip() {
+        let text = to_rle(&glider(), Some("B3/S23"));
+        let file = parse_rle(&text, GRID).unwrap();
+        assert_eq!(file.pattern, glider());
+        assert_eq!(file.rule.as_deref(), Some("B3/S23"));
+    }
+
+    #[test]
+    fn rle_round_trip_with_many_states() {
+        let pattern = Pattern {
+            width: 4,
+            height: 2,
+            cells: vec![0, 2, 24, 25, 255, 0, 0, 1],
+        };
+        let file = parse_rle(&to_rle(&pattern, None), GRID).unwrap();
+        assert_eq!(file.pattern, pattern);
+        assert_eq!(file.rule, None);
+    }
+
+    #[test]
+    fn rle_header_rule_with_bounded_grid() {
+        let file = parse_rle("x = 3, y = 3, rule = B3/S23:T10,10\nbo$2bo$3o!", GRID).unwrap();
+        assert_eq!(file.pattern, glider());
+        assert_eq!(file.rule.as_deref(), Some("B3/S23"));
+    }
+
+    #[test]
+    fn rle_header_size_is_kept_within_max_size() {
+        let file = parse_rle("x = 100, y = 50\no!", GRID).unwrap();
+        assert_eq!((file.pattern.width, file.pattern.height), (100, 50));
+        let file = parse_rle("x = 100, y = 50\no!", (10, 10)).unwrap();
+        assert_eq!((file.pattern.width, file.pattern.height), (10, 10));
+    }
+
+    #[test]
+    fn rle_too_big() {
+        assert!(parse_rle("5o99999999999999999999999o!", GRID).is_err());
+        assert!(parse_rle("1$99999999999999999999999$o!", GRID).is_err());
+        assert!(parse_rle(&format!("{}o!", MAX_GRID_SIZE + 1), GRID).is_err());
+        assert!(parse_rle(&format!("{}$o!", MAX_GRID_SIZE), GRID).is_err());
+        assert!(parse_rle("x = 99999999999999999999999, y = 1\no!", GRID).is_err());
+        assert!(parse_rle(&format!("x = 1, y = {}\no!", MAX_GRID_SIZE + 1), GRID).is_err());
+    }
+
+    #[test]
+    fn plaintext_round_trip() {
+        let file = parse_cells(&to_cells(&glider(), "glider")).unwrap();
+        assert_eq!(file.pattern, glider());
+        assert_eq!(file.rule, None);
+    }
+
+    #[test]
+    fn plaintext_too_big() {
+        let row = "O".repeat(MAX_GRID_SIZE + 1);
+        assert!(parse_cells(&row).is_err());
+    }
+
+    #[test]
+    fn life_106_round_trip() {
+        let text = to_life_106(&glider());
+        assert!(text.starts_with("#Life 1.06"));
+        let file = parse_life_106(&text).unwrap();
+        assert_eq!(file.pattern, glider());
+    }
+
+    #[test]
+    fn life_106_too_big() {
+        assert!(parse_life_106("#Life 1.06\n0 0\n99999 0\n").is_err());
+        assert!(parse_life_106("#Life 1.06\n-9223372036854775808 0\n9223372036854775807 0\n").is_err());
+    }
+
+    #[test]
+    fn life_105_round_trip() {
+        let file = parse_life_105(&to_life_105(&glider(), Some(CONWAY_RULE))).unwrap();
+        assert_eq!(file.pattern, glider());
+        assert_eq!(file.rule.as_deref(), Some(CONWAY_RULE));
+
+        let file = parse_life_105(&to_life_105(&glider(), Some("B36/S23"))).unwrap();
+        assert_eq!(file.pattern, glider());
+        assert_eq!(parse_rule(&file.rule.unwrap()), parse_rule("B36/S23"));
+    }
+
+    #[test]
+    fn life_105_wide_blocks_round_trip() {
+        let width = LIFE_105_LINE_LENGTH * 2 + 5;
+        let mut cells = vec![0; width * 2];
+        cells[0] = 1;
+        cells[width + width - 1] = 1;
+        let pattern = Pattern { width, height: 2, cells };
+        let file = parse_life_105(&to_life_105(&pattern, None)).unwrap();
+        assert_eq!(file.pattern, pattern);
+    }
+
+    #[test]
+    fn life_105_too_big() {
+        assert!(parse_life_105("#Life 1.05\n#P 9223372036854775807 0\n.**\n").is_err());
+        assert!(parse_life_105("#Life 1.05\n#P 0 9223372036854775807\n*\n*\n").is_err());
+        assert!(parse_life_105("#Life 1.05\n#P 0 0\n*\n#P 99999 0\n*\n").is_err());
+    }
+}
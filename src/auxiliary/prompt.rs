@@ -0,0 +1,54 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use winit::event::VirtualKeyCode;
+use winit_input_helper::{TextChar, WinitInputHelper};
+
+/// What happened to a prompt during the last step of input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Typed {
+    Editing,
+    Entered,
+    Cancelled,
+}
+
+/// A line of text typed into the window, like a file name, shown with a
+/// label in front of it.
+#[derive(Clone, Debug)]
+pub struct Prompt {
+    label: &'static str,
+    pub text: String,
+}
+
+impl Prompt {
+    /// A prompt starting out with `text` typed.
+    pub fn new(label: &'static str, text: String) -> Self {
+        Self { label, text }
+    }
+
+    /// Take the characters typed since the last step. Enter finishes the
+    /// prompt and Esc cancels it.
+    pub fn type_text(&mut self, input: &WinitInputHelper) -> Typed {
+        for typed in input.text() {
+            match typed {
+                TextChar::Char(c) if !c.is_control() => self.text.push(c),
+                TextChar::Char(_) => (),
+                TextChar::Back => {
+                    self.text.pop();
+                }
+            }
+        }
+        if input.key_pressed(VirtualKeyCode::Escape) {
+            Typed::Cancelled
+        } else if input.key_pressed(VirtualKeyCode::Return) || input.key_pressed(VirtualKeyCode::NumpadEnter) {
+            Typed::Entered
+        } else {
+            Typed::Editing
+        }
+    }
+
+    /// The label and text with a cursor after it.
+    pub fn line(&self) -> String {
+        format!("{}: {}_", self.label, self.text)
+    }
+}
@@ -16,4 +16,57 @@ pub fn generate_seed() -> (u64, u64) {
         NativeEndian::read_u64(&seed[0..8]),
         NativeEndian::read_u64(&seed[8..16]),
     )
-}
\ No newline at end of file
+}
+
+// splitmix64, so that neighboring seeds give unrelated generators
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A generator that always produces the same numbers for the same seed.
+pub fn seeded_rng(seed: u64) -> randomize::PCG32 {
+    // the increment has to be odd for a full period
+    (mix(seed), mix(!seed) | 1).into()
+}
+
+/// Hands out the seeds used to randomize an automaton.
+///
+/// The first seed is the one given on the command line, or a random one, and
+/// each after that is one more than the last. Every random state can then be
+/// recreated by starting with `--seed` set to the seed it was made from.
+#[derive(Clone, Copy, Debug)]
+pub struct Seeder {
+    seed: u64,
+    used: bool,
+}
+
+impl Seeder {
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed: seed.unwrap_or_else(|| generate_seed().0),
+            used: false,
+        }
+    }
+
+    /// The seed of the current random state, or the one that will be used
+    /// next if there hasn't been one yet.
+    pub fn current(&self) -> u64 {
+        self.seed
+    }
+
+    /// Whether any random state has been made yet.
+    pub fn used(&self) -> bool {
+        self.used
+    }
+
+    pub fn next_seed(&mut self) -> u64 {
+        if self.used {
+            self.seed = self.seed.wrapping_add(1);
+        }
+        self.used = true;
+        self.seed
+    }
+}
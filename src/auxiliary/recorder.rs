@@ -0,0 +1,97 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use crate::auxiliary::screenshot::upscale;
+
+// delay between frames of a recording in hundredths of a second
+const FRAME_DELAY: u16 = 4;
+
+
+/// Writes successive frames into an animated GIF.
+///
+/// Only every `frame_skip`th frame offered to `capture` is kept and the
+/// recording stops accepting frames once `max_frames` have been written. The
+/// file is finalized when the recorder is dropped.
+pub struct Recorder {
+    encoder: gif::Encoder<BufWriter<File>>,
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    scale: u32,
+    frame_skip: usize,
+    max_frames: usize,
+    frames_seen: usize,
+    frames_written: usize,
+}
+
+impl Recorder {
+    pub fn new(
+        path: &Path,
+        width: u32,
+        height: u32,
+        scale: u32,
+        frame_skip: usize,
+        max_frames: usize,
+    ) -> io::Result<Self> {
+        let scale = scale.max(1);
+        let too_big = || io::Error::other("frame is too large for a GIF");
+        let gif_width = u16::try_from(width * scale).map_err(|_| too_big())?;
+        let gif_height = u16::try_from(height * scale).map_err(|_| too_big())?;
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = gif::Encoder::new(file, gif_width, gif_height, &[])
+            .map_err(io::Error::other)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(io::Error::other)?;
+        Ok(Self {
+            encoder,
+            path: path.to_path_buf(),
+            width,
+            height,
+            scale,
+            frame_skip: frame_skip.max(1),
+            max_frames,
+            frames_seen: 0,
+            frames_written: 0,
+        })
+    }
+
+    /// Offer an RGBA frame to the recording. Returns `false` once the
+    /// recording is full and no more frames will be kept.
+    pub fn capture(&mut self, frame: &[u8]) -> io::Result<bool> {
+        if self.is_full() {
+            return Ok(false);
+        }
+        let keep = self.frames_seen.is_multiple_of(self.frame_skip);
+        self.frames_seen += 1;
+        if keep {
+            let mut data = upscale(frame, self.width, self.height, self.scale);
+            let mut gif_frame = gif::Frame::from_rgba_speed(
+                (self.width * self.scale) as u16,
+                (self.height * self.scale) as u16,
+                &mut data,
+                10,
+            );
+            gif_frame.delay = FRAME_DELAY;
+            self.encoder.write_frame(&gif_frame).map_err(io::Error::other)?;
+            self.frames_written += 1;
+        }
+        Ok(!self.is_full())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.frames_written >= self.max_frames
+    }
+
+    pub fn frames_written(&self) -> usize {
+        self.frames_written
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
@@ -0,0 +1,49 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+// Rulestrings for Life-like automata
+// https://conwaylife.com/wiki/Rulestring
+//
+// A rule is a pair of tables indexed by the number of live neighbors. The
+// first says when a dead cell is born and the second when a live cell survives.
+
+pub const CONWAY_RULE: &str = "B3/S23";
+
+fn parse_digits(digits: &str) -> Result<[bool;9], String> {
+    let mut arr = [false;9];
+    for c in digits.chars() {
+        match c.to_digit(10) {
+            Some(n) if n < 9 => arr[n as usize] = true,
+            _ => return Err(format!("invalid neighbor count '{}'", c)),
+        }
+    }
+    Ok(arr)
+}
+
+/// Parse a rulestring into its birth and survival tables.
+///
+/// Accepts B/S notation like `B36/S23` (in either order and any case) and
+/// the older S/B notation like `23/3`, where survival comes first.
+pub fn parse_rule(rule: &str) -> Result<([bool;9],[bool;9]), String> {
+    let rule = rule.trim().to_ascii_uppercase();
+    let (first, second) = rule
+        .split_once('/')
+        .ok_or_else(|| format!("rulestring '{}' needs a '/'", rule))?;
+    let (birth, survive) = match (first.strip_prefix('B'), second.strip_prefix('S')) {
+        (Some(b), Some(s)) => (b, s),
+        _ => match (first.strip_prefix('S'), second.strip_prefix('B')) {
+            (Some(s), Some(b)) => (b, s),
+            // Plain digits are survival/birth
+            _ => (second, first),
+        },
+    };
+    Ok((parse_digits(birth)?, parse_digits(survive)?))
+}
+
+/// The canonical B/S form of a rule, e.g. `B3/S23`.
+pub fn rule_to_string(birth: [bool;9], survive: [bool;9]) -> String {
+    let digits = |arr: [bool;9]| -> String {
+        (0..9).filter(|&n| arr[n]).map(|n| char::from(b'0' + n as u8)).collect()
+    };
+    format!("B{}/S{}", digits(birth), digits(survive))
+}
@@ -0,0 +1,667 @@
+//https://github.com/parasyte/pixels/tree/c2454b01abc11c007d4b9de8525195af942fef0d/examples/conway
+
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use log::{debug, error};
+use pixels::{Error, Pixels, SurfaceTexture};
+use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit_input_helper::WinitInputHelper;
+
+use crate::auxiliary::automaton::{Automaton, Help};
+use crate::auxiliary::headless::run_headless;
+use crate::auxiliary::history::History;
+use crate::auxiliary::hud::{columns, wrap, wrap_columns, Hud};
+use crate::auxiliary::options::{Options, MAX_GRID_SIZE};
+use crate::auxiliary::patterns::{place, read_pattern, write_pattern};
+use crate::auxiliary::prompt::{Prompt, Typed};
+use crate::auxiliary::randomizer::Seeder;
+use crate::auxiliary::recorder::Recorder;
+use crate::auxiliary::screenshot::{screenshot, timestamped_filename};
+use crate::auxiliary::selection::{fill, randomize_in, transform, Pattern, Selection};
+use crate::auxiliary::speed::Speed;
+use crate::auxiliary::camera::Camera;
+use crate::auxiliary::window::{create_window, MAX_VIEW_WIDTH, MAX_VIEW_HEIGHT};
+
+
+// Longest time spent on generations in one frame, so fast forward doesn't
+// freeze the window when the automaton can't keep up
+const UPDATE_BUDGET: Duration = Duration::from_millis(50);
+
+// Bigger worlds don't keep their recent generations, since saving the whole
+// grid every frame would slow them down too much
+const MAX_HISTORY_CELLS: usize = 1 << 22;
+
+// Keys that only change the view, the speed or what is shown, so pressing
+// them doesn't need the grid saved for undo. None of them are project keys,
+// and none of them edit the selection with Ctrl held, since keys are told
+// apart without their modifiers.
+fn edits_nothing(key: VirtualKeyCode) -> bool {
+    use VirtualKeyCode::*;
+    matches!(
+        key,
+        Escape | P | Space | S | G | H | Tab | Key0 | Numpad0
+            | Plus | Equals | NumpadAdd | Minus | NumpadSubtract
+            | Left | Right | Up | Down
+            | LShift | RShift | LControl | RControl | LAlt | RAlt | LWin | RWin
+    )
+}
+
+/// The keys every project responds to, as listed by the help overlay.
+pub const CONTROLS: &[(&str, &str)] = &[
+    ("C", "clear screen"),
+    ("P", "pause"),
+    ("R", "randomize screen with the next seed"),
+    ("S", "save screenshot"),
+    ("G", "start/stop GIF recording"),
+    ("Click and drag", "draw"),
+    ("Arrows or right drag", "move the view"),
+    ("Mouse wheel", "zoom"),
+    ("F", "fit the world in the window"),
+    ("TAB", "show/hide generation, population, rule, speed and seed"),
+    ("+ and -", "faster and slower"),
+    ("0", "switch between generations per frame and per second"),
+    ("Ctrl+Z and Ctrl+Y", "undo and redo"),
+    ("Shift and drag", "select cells"),
+    ("Ctrl+A and Ctrl+D", "select everything and nothing"),
+    ("Ctrl+C, Ctrl+X and Ctrl+V", "copy, cut and paste at the mouse"),
+    ("Ctrl+R", "rotate the selection clockwise"),
+    ("Ctrl+M and Ctrl+U", "mirror and flip the selection upside down"),
+    ("Ctrl+F and DELETE", "fill and clear the selection"),
+    ("Ctrl+N", "randomize the selection with the next seed"),
+    ("Ctrl+O", "load a .rle, .cells, .lif, .life or .mc pattern, which can also be dropped on the window"),
+    ("Ctrl+S", "save the selection or the whole grid as .rle, .cells, .lif, .life or .mc"),
+    ("BACKSPACE", "step back to before the last frame's generations, one at a time when frame by frame"),
+    ("H", "show/hide this help"),
+    ("SPACE", "frame by frame"),
+    ("ESC", "close screen"),
+];
+
+/// Run the automaton either in a window or headless, depending on `options`.
+pub fn launch<A: Automaton + 'static>(mut automaton: A, title: &str, options: &Options) -> Result<(), Error> {
+    // Presets keep a seed given on the command line for R
+    let mut seeder = Seeder::new(options.seed);
+    if automaton.starts_random() || (options.seed.is_some() && !automaton.has_preset()) {
+        automaton.randomize(seeder.next_seed());
+    }
+    if let Some(path) = &options.pattern {
+        let file = read_pattern(path, automaton.size())
+            .map_err(|e| io::Error::new(e.kind(), format!("could not load {}: {}", path.display(), e)))
+            .map_err(|e| Error::UserDefined(Box::new(e)))?;
+        place(&mut automaton, &file);
+    }
+    let title = automaton.title().unwrap_or_else(|| title.to_string());
+    let title = title.as_str();
+    if options.headless {
+        run_headless(automaton, title, options, seeder).map_err(|e| Error::UserDefined(Box::new(e)))
+    } else {
+        run(automaton, title, options, seeder)
+    }
+}
+
+fn seeded_title(title: &str, seeder: &Seeder) -> String {
+    format!("{} - seed {}", title, seeder.current())
+}
+
+// Load a saved state into the automaton, keeping the size it has now, which
+// may have changed since along with the window
+fn restore<A: Automaton>(automaton: &mut A, state: &[u8]) {
+    let (width, height) = automaton.size();
+    automaton.load_state(state);
+    if automaton.size() != (width, height) {
+        automaton.resize(width, height);
+    }
+}
+
+// What a file name typed into the prompt is for
+#[derive(Clone, Copy, Debug)]
+enum FileAction {
+    Load,
+    Save,
+}
+
+// Load a pattern file in place of the grid, saying whether it worked
+fn load_pattern<A: Automaton>(automaton: &mut A, path: &Path) -> bool {
+    match read_pattern(path, automaton.size()) {
+        Ok(file) => {
+            place(automaton, &file);
+            println!("loaded pattern from {}", path.display());
+            true
+        }
+        Err(e) => {
+            error!("could not load {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+// Save the cells in `area` to a pattern file, saying whether it worked
+fn save_pattern<A: Automaton>(automaton: &A, area: Selection, path: &Path) -> bool {
+    let pattern = Pattern::copy(automaton, area);
+    match write_pattern(path, &pattern, automaton.rulestring().as_deref()) {
+        Ok(()) => {
+            println!("saved pattern to {}", path.display());
+            true
+        }
+        Err(e) => {
+            error!("could not save {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+// The title, description and keys of a project followed by the shared
+// controls, as paragraphs to wrap
+fn help_lines(title: &str, help: &Help) -> Vec<String> {
+    let mut lines = vec![title.to_string(), String::new(), help.description.to_string()];
+    for keys in [help.keys, CONTROLS] {
+        lines.push(String::new());
+        lines.extend(keys.iter().map(|(key, action)| format!("{}: {}", key, action)));
+    }
+    lines
+}
+
+/// Open a window and run the automaton until the window is closed.
+///
+/// Handles the controls shared by every project (pause, frame step, clear,
+/// randomize, screenshots, recording and mouse drawing) and defers anything else to
+/// `Automaton::handle_keys`. R randomizes with the seed after the last one used,
+/// Tab shows or hides the HUD and H the help. The speed changes with + and -
+/// and 0 switches between generations per frame and generations per second.
+/// Any change made with the keys or the mouse can be undone with Ctrl+Z and
+/// redone with Ctrl+Y, and Backspace steps back through the states recent frames started from.
+/// Dragging with Shift held selects a rectangle of cells to copy, paste,
+/// transform, fill or randomize with Ctrl and a key, which is why the other
+/// letter keys are left alone while Ctrl is held. Ctrl+O and Ctrl+S ask for
+/// the name of a pattern file to load or save, and pattern files can also be
+/// dropped on the window.
+/// The camera zooms with the mouse wheel, pans with the arrow keys or by
+/// dragging with the right or middle button and F fits the world in the
+/// window. With `options.fit_window` the grid is resized along with the window.
+pub fn run<A: Automaton + 'static>(mut automaton: A, title: &str, options: &Options, mut seeder: Seeder) -> Result<(), Error> {
+    env_logger::init();
+    let event_loop = EventLoop::new();
+    let mut input = WinitInputHelper::new();
+    let (world_width, world_height) = automaton.size();
+    let (world_width, world_height) = (world_width as u32, world_height as u32);
+    let mut camera = Camera::new(world_width, world_height, MAX_VIEW_WIDTH, MAX_VIEW_HEIGHT);
+    let mut world = vec![0; (4 * world_width * world_height) as usize];
+    let (window, p_width, p_height, mut _hidpi_factor) =
+        create_window(
+            &seeded_title(title, &seeder),
+            camera.width,
+            camera.height,
+            &event_loop);
+    let title = title.to_string();
+    let mut shown_title = seeded_title(&title, &seeder);
+
+    let surface_texture = SurfaceTexture::new(p_width, p_height, &window);
+
+    let mut pixels = Pixels::new(camera.width, camera.height, surface_texture)?;
+    // Physical pixels per cell, kept when the grid follows the window
+    let cell_size = (p_width / camera.width).min(p_height / camera.height).max(1);
+    let fit_window = options.fit_window;
+    let mut paused = false;
+
+    let mut draw_state: Option<bool> = None;
+    let screenshot_scale = options.scale;
+    let recording_options = options.clone();
+    let mut recorder: Option<Recorder> = None;
+    // Set whenever a generation passes so recordings skip repeated frames
+    let mut stepped = true;
+    // Generations since the grid was last cleared or randomized
+    let mut generation: u64 = 0;
+    let mut hud = Hud::default();
+    let mut show_help = false;
+    let mut speed = Speed::new(options.rate);
+    let mut history = History::default();
+    // Set by key presses, which may change the grid
+    let mut key_down = false;
+    let mut selection: Option<Selection> = None;
+    // Corner the selection is being dragged from
+    let mut select_from: Option<(isize, isize)> = None;
+    let mut clipboard: Option<Pattern> = None;
+    // Takes the keys while a file name is typed
+    let mut prompt: Option<(FileAction, Prompt)> = None;
+    // Offered the next time a pattern is loaded
+    let mut pattern_path: Option<PathBuf> = None;
+
+    event_loop.run(move |event, _, control_flow| {
+        // The one and only event that winit_input_helper doesn't have for us...
+        if let Event::RedrawRequested(_) = event {
+            automaton.draw(&mut world);
+            camera.render(&world, pixels.get_frame());
+            if stepped {
+                if let Some(rec) = recorder.as_mut() {
+                    match rec.capture(pixels.get_frame()) {
+                        Ok(true) => (),
+                        Ok(false) => {
+                            println!("recording full, {} frames saved to {}", rec.frames_written(), rec.path().display());
+                            recorder = None;
+                        }
+                        Err(e) => {
+                            error!("recording failed: {}", e);
+                            recorder = None;
+                        }
+                    }
+                }
+                stepped = false;
+            }
+            // Drawn after recording so overlays stay out of animations
+            if let Some(selection) = selection {
+                selection.draw(&camera, pixels.get_frame());
+            }
+            if let Some((_, prompt)) = &prompt {
+                let lines = wrap(&prompt.line(), columns(camera.width));
+                hud.draw(pixels.get_frame(), camera.width, &lines);
+            } else if show_help {
+                let lines = wrap_columns(&help_lines(&title, automaton.help()), camera.width, camera.height);
+                hud.draw(pixels.get_frame(), camera.width, &lines);
+            } else if hud.visible {
+                let lines = [
+                    format!("generation {}", generation),
+                    format!("{} {}", automaton.population_name(), automaton.population()),
+                    automaton.rule_name(),
+                    format!("{:.1} gen/s, {}", hud.speed(), speed),
+                    format!("seed {}", seeder.current()),
+                ];
+                hud.draw(pixels.get_frame(), camera.width, &lines);
+            }
+            if pixels
+                .render()
+                .map_err(|e| error!("pixels.render() failed: {}", e))
+                .is_err()
+            {
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+        }
+
+        if let Event::WindowEvent {
+            event: WindowEvent::KeyboardInput {
+                input: KeyboardInput { state: ElementState::Pressed, virtual_keycode, .. },
+                ..
+            },
+            ..
+        } = event
+        {
+            key_down |= !virtual_keycode.is_some_and(edits_nothing);
+        }
+
+        // For everything else, for let winit_input_helper collect events to build its state.
+        // It returns `true` when it is time to update our game state and request a redraw.
+        if input.update(&event) {
+            let typing = prompt.is_some();
+            let pressed = |key| !typing && input.key_pressed(key);
+            // Close events
+            if pressed(VirtualKeyCode::Escape) || input.quit() {
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+            if pressed(VirtualKeyCode::P) {
+                paused = !paused;
+            }
+            if pressed(VirtualKeyCode::Space) {
+                // Space is frame-step, so ensure we're paused
+                paused = true;
+            }
+            // Going back and forth in time isn't an edit itself
+            let mut time_travel = None;
+            if input.held_control() && pressed(VirtualKeyCode::Z) {
+                time_travel = history.undo(generation, &automaton.save_state());
+                if time_travel.is_none() {
+                    println!("nothing to undo");
+                }
+            } else if input.held_control() && pressed(VirtualKeyCode::Y) {
+                time_travel = history.redo(generation, &automaton.save_state());
+                if time_travel.is_none() {
+                    println!("nothing to redo");
+                }
+            } else if pressed(VirtualKeyCode::Back) {
+                paused = true;
+                time_travel = history.step_back();
+                if time_travel.is_none() {
+                    println!("no earlier generations kept");
+                }
+            }
+            // Anything else done with a key that might edit the grid is
+            // compared with the state from before any keys were handled.
+            // Typed text only edits once entered, which is saved for itself.
+            let before = match time_travel {
+                Some((previous_generation, state)) => {
+                    restore(&mut automaton, &state);
+                    generation = previous_generation;
+                    None
+                }
+                None if key_down && !typing => Some(automaton.save_state()),
+                None => None,
+            };
+            let before_generation = generation;
+            key_down = false;
+            // Letters with Ctrl edit the selection instead
+            let plain = !typing && !input.held_control();
+            if plain && pressed(VirtualKeyCode::R) {
+                let seed = seeder.next_seed();
+                automaton.randomize(seed);
+                generation = 0;
+                println!("randomized with seed {}", seed);
+            }
+            if plain && pressed(VirtualKeyCode::C) {
+                automaton.clear();
+                generation = 0;
+            }
+            if pressed(VirtualKeyCode::Tab) {
+                hud.visible = !hud.visible;
+            }
+            if pressed(VirtualKeyCode::Equals)
+                || pressed(VirtualKeyCode::Plus)
+                || pressed(VirtualKeyCode::NumpadAdd)
+            {
+                speed.faster();
+                println!("speed {}", speed);
+            }
+            if pressed(VirtualKeyCode::Minus) || pressed(VirtualKeyCode::NumpadSubtract) {
+                speed.slower();
+                println!("speed {}", speed);
+            }
+            if pressed(VirtualKeyCode::Key0) || pressed(VirtualKeyCode::Numpad0) {
+                speed.toggle_mode();
+                println!("speed {}", speed);
+            }
+            if plain && pressed(VirtualKeyCode::H) {
+                show_help = !show_help;
+            }
+            if plain && pressed(VirtualKeyCode::S) {
+                // Match the integer scaling pixels uses to fit the window
+                let scale = screenshot_scale.unwrap_or_else(|| {
+                    let size = window.inner_size();
+                    (size.width / camera.width).min(size.height / camera.height).max(1)
+                });
+                // Redraw the frame without the HUD or help
+                camera.render(&world, pixels.get_frame());
+                match screenshot(pixels.get_frame(), camera.width, camera.height, scale) {
+                    Ok(path) => println!("saved screenshot to {}", path.display()),
+                    Err(e) => error!("screenshot failed: {}", e),
+                }
+            }
+            if plain && pressed(VirtualKeyCode::G) {
+                if let Some(rec) = recorder.take() {
+                    println!("recording stopped, {} frames saved to {}", rec.frames_written(), rec.path().display());
+                } else {
+                    let path = timestamped_filename("recording", "gif");
+                    match Recorder::new(
+                        &path,
+                        camera.width,
+                        camera.height,
+                        recording_options.scale.unwrap_or(1),
+                        recording_options.frame_skip,
+                        recording_options.max_frames,
+                    ) {
+                        Ok(rec) => {
+                            println!("recording to {}", path.display());
+                            recorder = Some(rec);
+                            stepped = true;
+                        }
+                        Err(e) => error!("could not start recording: {}", e),
+                    }
+                }
+            }
+            let step_x = (camera.width / 8).max(1) as f64;
+            let step_y = (camera.height / 8).max(1) as f64;
+            let keys = [
+                (VirtualKeyCode::Left, -step_x, 0.0),
+                (VirtualKeyCode::Right, step_x, 0.0),
+                (VirtualKeyCode::Up, 0.0, -step_y),
+                (VirtualKeyCode::Down, 0.0, step_y),
+            ];
+            for (key, dx, dy) in keys {
+                if pressed(key) {
+                    camera.pan(dx, dy);
+                }
+            }
+            if plain && pressed(VirtualKeyCode::F) {
+                camera.fit();
+            }
+            if plain {
+                automaton.handle_keys(&input);
+            }
+            // Handle mouse. This is a bit involved since support some simple
+            // line drawing (mostly because it makes nice looking patterns).
+            // Positions are found in pixels of the view, which may be outside
+            // it, and then go through the camera to find the cells.
+            let view_pos = |pos: (f32, f32)| match pixels.window_pos_to_pixel(pos) {
+                Ok((x, y)) => (x as isize, y as isize),
+                Err(pos) => pos,
+            };
+            let mouse_view = input.mouse().map(|(mx, my)| {
+                let (dx, dy) = input.mouse_diff();
+                (view_pos((mx, my)), view_pos((mx - dx, my - dy)))
+            });
+            if let Some(((mx, my), (px, py))) = mouse_view {
+                if input.mouse_held(1) || input.mouse_held(2) {
+                    camera.pan((px - mx) as f64, (py - my) as f64);
+                }
+                let scroll = input.scroll_diff();
+                if scroll != 0.0 {
+                    camera.zoom_at(scroll.signum() as i32, mx as f64 + 0.5, my as f64 + 0.5);
+                }
+            }
+            let (mouse_cell, mouse_prev_cell) = mouse_view
+                .map(|(mouse, prev)| {
+                    let (mx, my) = pixels.clamp_pixel_pos(mouse);
+                    let (px, py) = pixels.clamp_pixel_pos(prev);
+                    (
+                        camera.to_world(mx as isize, my as isize),
+                        camera.to_world(px as isize, py as isize),
+                    )
+                })
+                .unwrap_or_default();
+
+            if let Some((action, p)) = prompt.as_mut() {
+                match p.type_text(&input) {
+                    Typed::Editing => (),
+                    Typed::Cancelled => prompt = None,
+                    Typed::Entered => {
+                        let action = *action;
+                        let path = PathBuf::from(p.text.trim());
+                        prompt = None;
+                        let done = match action {
+                            FileAction::Load => {
+                                let before = automaton.save_state();
+                                let loaded = load_pattern(&mut automaton, &path);
+                                if loaded {
+                                    history.edited(generation, &before);
+                                }
+                                loaded
+                            }
+                            FileAction::Save => {
+                                let area = selection.unwrap_or_else(|| Selection::all(automaton.size()));
+                                save_pattern(&automaton, area, &path)
+                            }
+                        };
+                        if done {
+                            // Loading starts over, like clearing
+                            if let FileAction::Load = action {
+                                generation = 0;
+                            }
+                            pattern_path = Some(path);
+                        }
+                    }
+                }
+            }
+            if !typing && input.held_control() {
+                let size = automaton.size();
+                if pressed(VirtualKeyCode::A) {
+                    selection = Some(Selection::all(size));
+                }
+                if pressed(VirtualKeyCode::D) {
+                    selection = None;
+                }
+                if let Some(selected) = selection {
+                    if pressed(VirtualKeyCode::C) || pressed(VirtualKeyCode::X) {
+                        clipboard = Some(Pattern::copy(&automaton, selected));
+                        println!("copied {}x{} cells", selected.width, selected.height);
+                    }
+                    if pressed(VirtualKeyCode::X) {
+                        fill(&mut automaton, selected, 0);
+                    }
+                    if pressed(VirtualKeyCode::R) {
+                        selection = transform(&mut automaton, selected, Pattern::rotate);
+                    }
+                    if pressed(VirtualKeyCode::M) {
+                        selection = transform(&mut automaton, selected, Pattern::mirror);
+                    }
+                    if pressed(VirtualKeyCode::U) {
+                        selection = transform(&mut automaton, selected, Pattern::flip);
+                    }
+                    if pressed(VirtualKeyCode::F) {
+                        let value = automaton.fill_value();
+                        fill(&mut automaton, selected, value);
+                    }
+                    if pressed(VirtualKeyCode::N) {
+                        let seed = seeder.next_seed();
+                        randomize_in(&mut automaton, selected, seed);
+                        println!("randomized selection with seed {}", seed);
+                    }
+                }
+                if pressed(VirtualKeyCode::O) {
+                    let text = pattern_path.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+                    prompt = Some((FileAction::Load, Prompt::new("load pattern", text)));
+                }
+                if pressed(VirtualKeyCode::S) {
+                    let path = timestamped_filename("pattern", "rle");
+                    prompt = Some((FileAction::Save, Prompt::new("save pattern", path.display().to_string())));
+                }
+                if pressed(VirtualKeyCode::V) {
+                    if let Some(pattern) = &clipboard {
+                        // The mouse may be off the grid, so paste at the
+                        // nearest cell that isn't
+                        let at = Selection::between(mouse_cell, mouse_cell, size);
+                        selection = pattern.paste(&mut automaton, at.left, at.top);
+                    }
+                }
+            }
+            if pressed(VirtualKeyCode::Delete) {
+                if let Some(selected) = selection {
+                    fill(&mut automaton, selected, 0);
+                }
+            }
+            if let Some(before) = before {
+                if automaton.save_state() != before {
+                    history.edited(before_generation, &before);
+                }
+            }
+
+            if input.mouse_pressed(0) && input.held_shift() {
+                select_from = Some(mouse_cell);
+            }
+            if let Some(from) = select_from {
+                selection = Some(Selection::between(from, mouse_cell, automaton.size()));
+                if !input.mouse_held(0) {
+                    select_from = None;
+                }
+            } else if input.mouse_pressed(0) {
+                debug!("Mouse click at {:?}", mouse_cell);
+                history.edited(generation, &automaton.save_state());
+                draw_state = Some(automaton.toggle(mouse_cell.0, mouse_cell.1));
+            } else if let Some(draw_alive) = draw_state {
+                let release = input.mouse_released(0);
+                let held = input.mouse_held(0);
+                debug!("Draw at {:?} => {:?}", mouse_prev_cell, mouse_cell);
+                debug!("Mouse held {:?}, release {:?}", held, release);
+                // If they either released (finishing the drawing) or are still
+                // in the middle of drawing, keep going.
+                if release || held {
+                    debug!("Draw line of {:?}", draw_alive);
+                    automaton.set_line(
+                        mouse_prev_cell.0,
+                        mouse_prev_cell.1,
+                        mouse_cell.0,
+                        mouse_cell.1,
+                        draw_alive,
+                    );
+                }
+                // If they let go or are otherwise not clicking anymore, stop drawing.
+                if release || !held {
+                    debug!("Draw end");
+                    draw_state = None;
+                }
+            }
+            if let Some(path) = input.dropped_file() {
+                let before = automaton.save_state();
+                if load_pattern(&mut automaton, &path) {
+                    history.edited(generation, &before);
+                    generation = 0;
+                    pattern_path = Some(path);
+                }
+            }
+            // Adjust high DPI factor
+            if let Some(factor) = input.scale_factor_changed() {
+                _hidpi_factor = factor;
+            }
+            // Resize the window
+            if let Some(size) = input.window_resized() {
+                pixels.resize_surface(size.width, size.height);
+                // A minimized window has no size, so the grid is left alone
+                if fit_window && size.width > 0 && size.height > 0 {
+                    let width = (size.width / cell_size).clamp(1, MAX_GRID_SIZE as u32);
+                    let height = (size.height / cell_size).clamp(1, MAX_GRID_SIZE as u32);
+                    let old_size = automaton.size();
+                    if old_size != (width as usize, height as usize) {
+                        automaton.resize(width as usize, height as usize);
+                    }
+                    // Some grids round the size they are given, so it may not
+                    // have changed after all
+                    if automaton.size() != old_size {
+                        let (width, height) = automaton.size();
+                        let (width, height) = (width as u32, height as u32);
+                        camera = Camera::new(width, height, MAX_VIEW_WIDTH, MAX_VIEW_HEIGHT);
+                        world = vec![0; (4 * width * height) as usize];
+                        pixels.resize_buffer(camera.width, camera.height);
+                        selection = None;
+                        select_from = None;
+                        // Frames of the new size can't go into the same animation
+                        if let Some(rec) = recorder.take() {
+                            println!("recording stopped by resize, {} frames saved to {}", rec.frames_written(), rec.path().display());
+                        }
+                        debug!("Grid resized to {}x{}", width, height);
+                    }
+                }
+            }
+            let generations = if pressed(VirtualKeyCode::Space) {
+                1
+            } else if paused {
+                speed.pause();
+                0
+            } else {
+                speed.generations()
+            };
+            let (width, height) = automaton.size();
+            if generations > 0 && width * height <= MAX_HISTORY_CELLS {
+                history.stepped(generation, &automaton.save_state());
+            }
+            let start = Instant::now();
+            for _ in 0..generations {
+                automaton.update();
+                generation += 1;
+                hud.count_generation();
+                stepped = true;
+                if start.elapsed() >= UPDATE_BUDGET {
+                    break;
+                }
+            }
+            // Follow new seeds, and rules from patterns or keys
+            let current_title = seeded_title(&automaton.title().unwrap_or_else(|| title.clone()), &seeder);
+            if current_title != shown_title {
+                window.set_title(&current_title);
+                shown_title = current_title;
+            }
+            window.request_redraw();
+        }
+    });
+}
@@ -0,0 +1,64 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+
+/// Blow up an RGBA frame by an integer factor using nearest neighbor sampling
+/// so each cell becomes a `scale` by `scale` block, like in the window.
+pub fn upscale(frame: &[u8], width: u32, height: u32, scale: u32) -> Vec<u8> {
+    debug_assert_eq!(frame.len(), (4 * width * height) as usize);
+    let scale = scale.max(1) as usize;
+    let (width, height) = (width as usize, height as usize);
+    let mut out = Vec::with_capacity(frame.len() * scale * scale);
+    for y in 0..height {
+        let row = &frame[4 * y * width..4 * (y + 1) * width];
+        let start = out.len();
+        for pix in row.chunks_exact(4) {
+            for _ in 0..scale {
+                out.extend_from_slice(pix);
+            }
+        }
+        let end = out.len();
+        for _ in 1..scale {
+            out.extend_from_within(start..end);
+        }
+    }
+    out
+}
+
+/// Encode an RGBA frame buffer as a PNG, upscaled by an integer factor.
+pub fn save_png(path: &Path, frame: &[u8], width: u32, height: u32, scale: u32) -> io::Result<()> {
+    let scale = scale.max(1);
+    let data = upscale(frame, width, height, scale);
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, width * scale, height * scale);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder
+        .write_header()
+        .map_err(io::Error::other)?;
+    writer
+        .write_image_data(&data)
+        .map_err(io::Error::other)
+}
+
+/// A filename like `screenshot_1634433012345.png` that won't collide with
+/// earlier screenshots.
+pub fn timestamped_filename(prefix: &str, extension: &str) -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    PathBuf::from(format!("{}_{}.{}", prefix, millis, extension))
+}
+
+/// Save the frame to a new timestamped PNG in the working directory.
+pub fn screenshot(frame: &[u8], width: u32, height: u32, scale: u32) -> io::Result<PathBuf> {
+    let path = timestamped_filename("screenshot", "png");
+    save_png(&path, frame, width, height, scale)?;
+    Ok(path)
+}
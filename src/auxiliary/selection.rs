@@ -0,0 +1,177 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use crate::auxiliary::automaton::Automaton;
+use crate::auxiliary::camera::Camera;
+use crate::auxiliary::randomizer::seeded_rng;
+
+// Selections are edited through `Automaton::cell` and `Automaton::set_cell`,
+// so they work the same on every grid whatever its cells hold
+
+const OUTLINE_COLOR: [u8;4] = [0xff, 0xd0, 0x00, 0xff];
+
+/// A rectangle of cells, always inside the grid and never empty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub left: usize,
+    pub top: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Selection {
+    /// The cells between two corners, both included, cut down to a grid of
+    /// `size`.
+    pub fn between(a: (isize, isize), b: (isize, isize), size: (usize, usize)) -> Self {
+        let clamp = |n: isize, len: usize| n.clamp(0, len as isize - 1) as usize;
+        let (ax, bx) = (clamp(a.0, size.0), clamp(b.0, size.0));
+        let (ay, by) = (clamp(a.1, size.1), clamp(b.1, size.1));
+        Self {
+            left: ax.min(bx),
+            top: ay.min(by),
+            width: ax.max(bx) - ax.min(bx) + 1,
+            height: ay.max(by) - ay.min(by) + 1,
+        }
+    }
+
+    /// The whole of a grid of `size`.
+    pub fn all(size: (usize, usize)) -> Self {
+        Self {
+            left: 0,
+            top: 0,
+            width: size.0,
+            height: size.1,
+        }
+    }
+
+    fn cells(self) -> impl Iterator<Item = (usize, usize)> {
+        (self.top..self.top + self.height)
+            .flat_map(move |y| (self.left..self.left + self.width).map(move |x| (x, y)))
+    }
+
+    /// Outline the selection in a view of the world through `camera`, just
+    /// outside the cells so they can still be seen.
+    pub fn draw(&self, camera: &Camera, frame: &mut [u8]) {
+        let (left, top) = camera.to_view(self.left as f64, self.top as f64);
+        let (right, bottom) = camera.to_view((self.left + self.width) as f64, (self.top + self.height) as f64);
+        let (left, top) = (left.floor() as i64 - 1, top.floor() as i64 - 1);
+        let (right, bottom) = (right.ceil() as i64, bottom.ceil() as i64);
+        let (width, height) = (camera.width as i64, camera.height as i64);
+        let mut plot = |x: i64, y: i64| {
+            if (0..width).contains(&x) && (0..height).contains(&y) {
+                let i = 4 * (x + y * width) as usize;
+                frame[i..i + 4].copy_from_slice(&OUTLINE_COLOR);
+            }
+        };
+        for x in left.max(-1)..=right.min(width) {
+            plot(x, top);
+            plot(x, bottom);
+        }
+        for y in top.max(-1)..=bottom.min(height) {
+            plot(left, y);
+            plot(right, y);
+        }
+    }
+}
+
+/// Cells copied out of a grid, to be pasted back in anywhere.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    /// Values as `Automaton::cell` gives them, row by row
+    pub cells: Vec<u32>,
+}
+
+impl Pattern {
+    /// Copy the selected cells.
+    pub fn copy<A: Automaton>(automaton: &A, selection: Selection) -> Self {
+        Self {
+            width: selection.width,
+            height: selection.height,
+            cells: selection.cells().map(|(x, y)| automaton.cell(x, y)).collect(),
+        }
+    }
+
+    /// Paste with the top left corner at (left, top), leaving out whatever
+    /// falls off the grid. Returns where the pattern ended up, if anywhere.
+    pub fn paste<A: Automaton>(&self, automaton: &mut A, left: usize, top: usize) -> Option<Selection> {
+        let (width, height) = automaton.size();
+        if left >= width || top >= height {
+            return None;
+        }
+        let selection = Selection {
+            left,
+            top,
+            width: self.width.min(width - left),
+            height: self.height.min(height - top),
+        };
+        for (x, y) in selection.cells() {
+            automaton.set_cell(x, y, self.cells[x - left + (y - top) * self.width]);
+        }
+        Some(selection)
+    }
+
+    // The pattern rearranged into a new size, each cell taken from the cell
+    // of this one that `from` gives for its position
+    fn remap(&self, width: usize, height: usize, from: impl Fn(usize, usize) -> (usize, usize)) -> Self {
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let (fx, fy) = from(x, y);
+                cells.push(self.cells[fx + fy * self.width]);
+            }
+        }
+        Self { width, height, cells }
+    }
+
+    /// The part of the pattern `width` by `height` cells with its top left
+    /// corner at (left, top).
+    pub fn crop(&self, left: usize, top: usize, width: usize, height: usize) -> Self {
+        self.remap(width, height, |x, y| (x + left, y + top))
+    }
+
+    /// Turn a quarter turn clockwise.
+    pub fn rotate(&self) -> Self {
+        let height = self.height;
+        self.remap(self.height, self.width, |x, y| (y, height - 1 - x))
+    }
+
+    /// Flip left to right.
+    pub fn mirror(&self) -> Self {
+        let width = self.width;
+        self.remap(self.width, self.height, |x, y| (width - 1 - x, y))
+    }
+
+    /// Flip top to bottom.
+    pub fn flip(&self) -> Self {
+        let height = self.height;
+        self.remap(self.width, self.height, |x, y| (x, height - 1 - y))
+    }
+}
+
+/// Set every selected cell to `value`.
+pub fn fill<A: Automaton>(automaton: &mut A, selection: Selection, value: u32) {
+    for (x, y) in selection.cells() {
+        automaton.set_cell(x, y, value);
+    }
+}
+
+/// Randomize only the selected cells, the same way `Automaton::randomize`
+/// would.
+pub fn randomize_in<A: Automaton>(automaton: &mut A, selection: Selection, seed: u64) {
+    let mut rng = seeded_rng(seed);
+    for (x, y) in selection.cells() {
+        let value = automaton.random_cell(&mut rng);
+        automaton.set_cell(x, y, value);
+    }
+}
+
+/// Transform the selected cells in place, keeping the top left corner where
+/// it is, and return the new selection. Cells left behind when the shape
+/// changes are cleared.
+pub fn transform<A: Automaton>(automaton: &mut A, selection: Selection, f: impl Fn(&Pattern) -> Pattern) -> Option<Selection> {
+    let pattern = f(&Pattern::copy(automaton, selection));
+    fill(automaton, selection, 0);
+    pattern.paste(automaton, selection.left, selection.top)
+}
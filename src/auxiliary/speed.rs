@@ -0,0 +1,125 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::fmt;
+use std::time::Instant;
+
+// Slowest slow motion, 2^6 frames per generation
+const MIN_LEVEL: i32 = -6;
+// Fastest fast forward, 2^10 generations per frame
+const MAX_LEVEL: i32 = 10;
+
+const MIN_RATE: f64 = 0.25;
+const MAX_RATE: f64 = 1_000_000.0;
+// Target rate when switching to it without one given on the command line
+const DEFAULT_RATE: f64 = 60.0;
+// Time a target rate can fall behind before the missed generations are
+// dropped, so a slow automaton doesn't pile up generations it can never run
+const MAX_LAG: f64 = 0.25;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    /// 2^level generations per frame when positive and one generation every
+    /// 2^-level frames when negative
+    PerFrame(i32),
+    /// Generations per second, whatever the frame rate
+    Rate(f64),
+}
+
+/// How fast the simulation runs compared to the redraws of the window.
+#[derive(Clone, Debug)]
+pub struct Speed {
+    mode: Mode,
+    // last target rate, kept while running per frame
+    rate: f64,
+    // frames since the last generation in slow motion
+    frames: u32,
+    // generations the target rate is behind by at `since`, including the
+    // fraction of one carried over between frames
+    owed: f64,
+    since: Instant,
+}
+
+impl Speed {
+    /// One generation per frame, or `rate` generations per second if given.
+    pub fn new(rate: Option<f64>) -> Self {
+        let rate = rate.map(|rate| rate.clamp(MIN_RATE, MAX_RATE));
+        Self {
+            mode: rate.map_or(Mode::PerFrame(0), Mode::Rate),
+            rate: rate.unwrap_or(DEFAULT_RATE),
+            frames: 0,
+            owed: 0.0,
+            since: Instant::now(),
+        }
+    }
+
+    /// How many generations to run this frame.
+    pub fn generations(&mut self) -> u32 {
+        match self.mode {
+            Mode::PerFrame(level) if level >= 0 => 1 << level,
+            Mode::PerFrame(level) => {
+                self.frames += 1;
+                if self.frames >= 1 << -level {
+                    self.frames = 0;
+                    1
+                } else {
+                    0
+                }
+            }
+            Mode::Rate(rate) => {
+                let now = Instant::now();
+                let elapsed = now.duration_since(self.since).as_secs_f64();
+                self.since = now;
+                self.owed = (self.owed + elapsed * rate).min(rate * MAX_LAG + 1.0);
+                let due = self.owed.floor();
+                self.owed -= due;
+                due as u32
+            }
+        }
+    }
+
+    /// Forget the time spent paused, so a target rate doesn't catch up on it.
+    pub fn pause(&mut self) {
+        self.since = Instant::now();
+        self.owed = 0.0;
+    }
+
+    pub fn faster(&mut self) {
+        self.change(1);
+    }
+
+    pub fn slower(&mut self) {
+        self.change(-1);
+    }
+
+    // Double or halve the speed
+    fn change(&mut self, step: i32) {
+        self.frames = 0;
+        self.mode = match self.mode {
+            Mode::PerFrame(level) => Mode::PerFrame((level + step).clamp(MIN_LEVEL, MAX_LEVEL)),
+            Mode::Rate(rate) => {
+                self.rate = (rate * 2f64.powi(step)).clamp(MIN_RATE, MAX_RATE);
+                Mode::Rate(self.rate)
+            }
+        };
+    }
+
+    /// Switch between a speed relative to the frame rate and a target rate.
+    pub fn toggle_mode(&mut self) {
+        self.pause();
+        self.mode = match self.mode {
+            Mode::PerFrame(_) => Mode::Rate(self.rate),
+            Mode::Rate(_) => Mode::PerFrame(0),
+        };
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mode {
+            Mode::PerFrame(level) if level >= 0 => write!(f, "{} per frame", 1 << level),
+            Mode::PerFrame(level) => write!(f, "1 per {} frames", 1 << -level),
+            Mode::Rate(rate) => write!(f, "target {} gen/s", rate),
+        }
+    }
+}
@@ -7,12 +7,17 @@ use winit::dpi::{LogicalPosition, LogicalSize, PhysicalSize};
 use winit::event_loop::EventLoop;
 
 
+// default size of the world in cells
 pub const SCREEN_WIDTH: u32 = 360;
 pub const SCREEN_HEIGHT: u32 = 240;
 
+// bigger worlds are seen through a view of at most this many cells
+pub const MAX_VIEW_WIDTH: u32 = 1280;
+pub const MAX_VIEW_HEIGHT: u32 = 800;
+
 // COPYPASTE: ideally this could be shared.
 
-/// Create a window for the game.
+/// Create a window for a view of `width` by `height` pixels.
 ///
 /// Automatically scales the window to cover about 2/3 of the monitor height.
 ///
@@ -22,6 +27,8 @@ pub const SCREEN_HEIGHT: u32 = 240;
 /// `width` and `height` are in `PhysicalSize` units.
 pub fn create_window(
     title: &str,
+    width: u32,
+    height: u32,
     event_loop: &EventLoop<()>,
 ) -> (winit::window::Window, u32, u32, f64) {
     // Create a hidden window so we can estimate a good default window size
@@ -33,8 +40,8 @@ pub fn create_window(
     let hidpi_factor = window.scale_factor();
 
     // Get dimensions
-    let width = SCREEN_WIDTH as f64;
-    let height = SCREEN_HEIGHT as f64;
+    let width = width as f64;
+    let height = height as f64;
     let (monitor_width, monitor_height) = {
         if let Some(monitor) = window.current_monitor() {
             let size = monitor.size().to_logical(hidpi_factor);
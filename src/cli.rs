@@ -0,0 +1,216 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use pixels::Error;
+
+use crate::auxiliary::options::Options;
+use crate::auxiliary::rulestring::{parse_rule, CONWAY_RULE};
+use crate::projects;
+use crate::projects::elementary::Rule1D;
+use crate::projects::margolus::BlockRule;
+use crate::projects::sandpiles::Preset;
+
+
+pub const USAGE: &str = "\
+Usage: pixel_art_mine [PROJECT] [FLAGS] [OPTIONS]
+
+Without a project an interactive menu is shown.
+
+Projects:
+  sandpiles [--preset pile|line|identity|max]
+  elementary --rule N                 rule below 256
+  life [--rule RULESTRING]            like B36/S23, Conway's Life by default
+  totalistic --code N                 code below 512
+  outer-totalistic --code N | --rule RULESTRING
+  critters
+  rotator
+  multicolor [--colors K] [--radius R] [--totalistic] --code N
+  margolus --rule tron|bbm|critters|rotation | --even TABLE [--odd TABLE]
+                                      TABLE is 16 comma separated blocks
+
+Options:
+  --headless --steps N --output PATH --scale N --frame-skip N --max-frames N
+  --boundary NAME --seed N --width N --height N --fit-window --rate N
+  --pattern PATH
+
+With --seed every project starts from a random state made from that seed,
+except sandpiles, which keep their preset and use the seed when R is pressed.
+With --fit-window the grid grows and shrinks with the window.
+Sandpiles only take the dead (a sink), torus or mirror boundary.
+Margolus grids are rounded down to an even width and height.
+With --rate the simulation aims for N generations per second.
+With --pattern the grid starts from an RLE, plaintext, Life 1.05, Life 1.06
+or Macrocell file, in the rule the file gives if the project can follow it.";
+
+/// A project and everything needed to start it.
+pub enum Command {
+    Sandpiles(Preset),
+    Elementary(u8),
+    Life([bool;9], [bool;9]),
+    Totalistic(u32),
+    OuterTotalistic(u32),
+    OneDimensional(Rule1D),
+    Margolus(BlockRule),
+}
+
+pub fn run(command: Command, options: &Options) -> Result<(), Error> {
+    match command {
+        Command::Sandpiles(preset) => projects::sandpiles::run_piles(preset, options),
+        Command::Elementary(rule) => projects::elementary::run_elementary(rule, options),
+        Command::Life(birth, survive) => projects::life::run_life(birth, survive, options),
+        Command::Totalistic(code) => projects::totalistic::run_totalistic(code, options),
+        Command::OuterTotalistic(code) => projects::outer_totalistic::run_outer_totalistic(code, options),
+        Command::OneDimensional(rule) => projects::elementary::run_one_dimensional(rule, options),
+        Command::Margolus(rule) => projects::margolus::run_margolus(rule, options),
+    }
+}
+
+// Flags each project accepts on top of the shared options. `--totalistic` is
+// the only one without a value.
+fn project_flags(project: &str) -> Option<&'static [&'static str]> {
+    match project {
+        "sandpiles" => Some(&["--preset"]),
+        "elementary" | "life" => Some(&["--rule"]),
+        "totalistic" => Some(&["--code"]),
+        "outer-totalistic" => Some(&["--code", "--rule"]),
+        "critters" | "rotator" => Some(&[]),
+        "multicolor" => Some(&["--colors", "--radius", "--code", "--totalistic"]),
+        "margolus" => Some(&["--rule", "--even", "--odd"]),
+        _ => None,
+    }
+}
+
+/// Parse the arguments after the program name. The command is None when no
+/// project was named and the menu should be used instead.
+pub fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<(Option<Command>, Options), String> {
+    let mut args = args.peekable();
+    let project = match args.peek() {
+        Some(arg) if !arg.starts_with("--") => args.next(),
+        _ => None,
+    };
+    let allowed = match project.as_deref() {
+        Some(name) => project_flags(name).ok_or_else(|| format!("unknown project: {}", name))?,
+        None => &[],
+    };
+    let mut options = Options::default();
+    let mut flags = HashMap::new();
+    while let Some(arg) = args.next() {
+        if let Some(&flag) = allowed.iter().find(|&&f| f == arg) {
+            let value = if flag == "--totalistic" {
+                String::new()
+            } else {
+                args.next()
+                    .filter(|value| !value.starts_with("--"))
+                    .ok_or_else(|| format!("{} needs a value", flag))?
+            };
+            flags.insert(flag, value);
+        } else if !options.parse_flag(&arg, &mut args)? {
+            return Err(format!("unknown argument: {}", arg));
+        }
+    }
+    let command = match project {
+        Some(name) => Some(build_command(&name, &flags)?),
+        None => None,
+    };
+    if let Some(Command::Sandpiles(_)) = command {
+        projects::sandpiles::check_boundary(options.boundary)?;
+        // Without R to press the seed would never be used
+        if options.headless && options.seed.is_some() {
+            return Err("sandpiles start from a preset, so --seed does nothing with --headless".to_string());
+        }
+    }
+    Ok((command, options))
+}
+
+fn number<T: FromStr>(flags: &HashMap<&str, String>, flag: &str) -> Result<Option<T>, String> {
+    flags
+        .get(flag)
+        .map(|value| value.parse().map_err(|_| format!("invalid {}: {}", flag, value)))
+        .transpose()
+}
+
+fn required<T>(value: Option<T>, flag: &str) -> Result<T, String> {
+    value.ok_or_else(|| format!("{} is required", flag))
+}
+
+fn build_command(project: &str, flags: &HashMap<&str, String>) -> Result<Command, String> {
+    let rule = flags.get("--rule");
+    match project {
+        "sandpiles" => {
+            let preset = flags.get("--preset").map_or("pile", String::as_str);
+            Ok(Command::Sandpiles(Preset::from_name(preset)?))
+        }
+        "elementary" => {
+            let code: u32 = required(number(flags, "--rule")?, "--rule")?;
+            let code = u8::try_from(code).map_err(|_| "rule must be less than 256".to_string())?;
+            Ok(Command::Elementary(code))
+        }
+        "life" => {
+            let (birth, survive) = parse_rule(rule.map_or(CONWAY_RULE, String::as_str))?;
+            Ok(Command::Life(birth, survive))
+        }
+        "totalistic" => {
+            let code = required(number(flags, "--code")?, "--code")?;
+            if code >= 512 {
+                return Err("code must be less than 512".to_string());
+            }
+            Ok(Command::Totalistic(code))
+        }
+        "outer-totalistic" => {
+            let code = match (number(flags, "--code")?, rule) {
+                (Some(_), Some(_)) => return Err("give either --code or --rule".to_string()),
+                (Some(code), None) => code,
+                (None, Some(rule)) => {
+                    let (birth, survive) = parse_rule(rule)?;
+                    projects::outer_totalistic::rule_to_code(birth, survive)
+                }
+                (None, None) => return Err("--code or --rule is required".to_string()),
+            };
+            if code >= 262144 {
+                return Err("code must be less than 262144".to_string());
+            }
+            Ok(Command::OuterTotalistic(code))
+        }
+        "critters" => Ok(Command::Margolus(BlockRule::critters())),
+        "rotator" => Ok(Command::Margolus(BlockRule::single_rotation())),
+        "multicolor" => {
+            let colors = number(flags, "--colors")?.unwrap_or(3);
+            let radius = number(flags, "--radius")?.unwrap_or(1);
+            let code = required(flags.get("--code"), "--code")?;
+            let rule = Rule1D::new(colors, radius, flags.contains_key("--totalistic"), code)?;
+            Ok(Command::OneDimensional(rule))
+        }
+        "margolus" => {
+            let rule = match (rule, flags.get("--even")) {
+                (Some(_), Some(_)) => return Err("give either --rule or --even".to_string()),
+                (Some(name), None) => BlockRule::named(name)
+                    .ok_or_else(|| format!("unknown rule '{}', expected tron, bbm, critters or rotation", name))?,
+                (None, Some(even)) => {
+                    let even = parse_block_table(even)?;
+                    let odd = match flags.get("--odd") {
+                        Some(odd) => parse_block_table(odd)?,
+                        None => even,
+                    };
+                    BlockRule::new("Custom Margolus", even, odd)?
+                }
+                (None, None) => return Err("--rule or --even is required".to_string()),
+            };
+            Ok(Command::Margolus(rule))
+        }
+        _ => Err(format!("unknown project: {}", project)),
+    }
+}
+
+pub fn parse_block_table(text: &str) -> Result<[u8;16], String> {
+    let entries = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.parse::<u8>().map_err(|_| format!("invalid block '{}'", t)))
+        .collect::<Result<Vec<u8>, String>>()?;
+    entries
+        .try_into()
+        .map_err(|v: Vec<u8>| format!("expected 16 blocks, found {}", v.len()))
+}
@@ -5,66 +5,235 @@
 use std::io;
 use pixels::Error;
 mod projects;
+mod cli;
 pub mod auxiliary;
 
-fn select_number_lt(maximum: u32) -> u32 {
+use auxiliary::automaton::Help;
+use auxiliary::options::Options;
+use auxiliary::rulestring::{parse_rule, CONWAY_RULE};
+use cli::{parse_block_table, Command};
+
+// None once stdin is closed
+fn select_number_lt(maximum: u32) -> Option<u32> {
+    loop {
+        match read_trimmed_line()?.parse() {
+            Ok(n) if n < maximum => return Some(n),
+            Ok(_) => println!("Must be less than {}.", maximum),
+            Err(_) => println!("Must input a number."),
+        }
+    }
+}
+
+
+// None once stdin is closed
+fn read_trimmed_line() -> Option<String> {
+    let mut text = String::new();
+    let read = io::stdin().read_line(&mut text).expect("Failed to read line");
+    if read == 0 {
+        return None
+    }
+    Some(text.trim().to_string())
+}
+
+fn select_rule_1d() -> Option<projects::elementary::Rule1D> {
+    use projects::elementary::{Rule1D, MAX_COLORS, MAX_RADIUS};
+    loop {
+        println!("Please specify number of colors from 2 to {}", MAX_COLORS);
+        let colors = read_trimmed_line()?.parse().unwrap_or(0);
+        println!("Please specify radius from 1 to {}", MAX_RADIUS);
+        let radius = read_trimmed_line()?.parse().unwrap_or(0);
+        println!("Totalistic? (y/n)");
+        let totalistic = read_trimmed_line()? == "y";
+        let max_code = match Rule1D::new(colors, radius, totalistic, "0") {
+            Ok(rule) => rule.max_code(),
+            Err(e) => {
+                println!("{}", e);
+                continue
+            }
+        };
+        loop {
+            println!("Please specify rule code at most {}", max_code);
+            match Rule1D::new(colors, radius, totalistic, &read_trimmed_line()?) {
+                Ok(rule) => return Some(rule),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+}
+
+// A blank line gives the center pile
+fn select_sandpile_preset() -> Option<projects::sandpiles::Preset> {
+    use projects::sandpiles::Preset;
+    loop {
+        println!("Please specify pile, line, identity or max (blank for pile)");
+        let text = read_trimmed_line()?;
+        let text = if text.is_empty() { "pile" } else { text.as_str() };
+        match Preset::from_name(text) {
+            Ok(preset) => return Some(preset),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+// A blank line gives Conway's Life
+fn select_life_rule() -> Option<([bool;9],[bool;9])> {
+    loop {
+        println!("Please specify a rulestring like B36/S23 or 23/3 (blank for {})", CONWAY_RULE);
+        let text = read_trimmed_line()?;
+        let text = if text.is_empty() { CONWAY_RULE } else { text.as_str() };
+        match parse_rule(text) {
+            Ok(rule) => return Some(rule),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+// Either a rule code or a rulestring, which is converted to its code
+fn select_outer_totalistic_code() -> Option<u32> {
     loop {
-        let mut text = String::new();
-        io::stdin().read_line(&mut text).expect("Failed to read line");
-        let n = text.trim().parse().unwrap();
-        if n >= maximum {
+        println!("Please specify rule code less than 262144 or a rulestring like B36/S23");
+        let text = read_trimmed_line()?;
+        if let Ok(n) = text.parse::<u32>() {
+            if n < 262144 {
+                return Some(n)
+            }
+            println!("rule code must be less than 262144");
             continue
         }
-        return n
+        match parse_rule(&text) {
+            Ok((birth, survive)) => return Some(projects::outer_totalistic::rule_to_code(birth, survive)),
+            Err(e) => println!("{}", e),
+        }
     }
 }
 
+fn select_block_rule() -> Option<projects::margolus::BlockRule> {
+    use projects::margolus::BlockRule;
+    loop {
+        println!("Please specify tron, bbm, critters, rotation or a table of 16 blocks");
+        let text = read_trimmed_line()?;
+        if let Some(rule) = BlockRule::named(&text) {
+            return Some(rule)
+        }
+        let even = match parse_block_table(&text) {
+            Ok(table) => table,
+            Err(e) => {
+                println!("{}", e);
+                continue
+            }
+        };
+        loop {
+            println!("Please specify a table of 16 blocks for the odd phase (blank to use the same table)");
+            let text = read_trimmed_line()?;
+            let odd = if text.is_empty() {
+                Ok(even)
+            } else {
+                parse_block_table(&text)
+            };
+            match odd.and_then(|odd| BlockRule::new("Custom Margolus", even, odd)) {
+                Ok(rule) => return Some(rule),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+}
 
-fn select_animation(input: &str) -> Result<(),Error> {
+// Prints a description of the project and asks for its settings, None if
+// there is nothing to run
+fn select_animation(input: &str) -> Option<Command> {
     match input {
         "1" => {
-            println!("Sandpiles are a very simple 2D cellular automata in which a pile with four or more grains drops one grain into each of its four immediate neightbors. Despite this extremely simple rule Sandpiles create durable patterns and shapes.");
-            projects::sandpiles::run_piles()
+            print_help(&projects::sandpiles::HELP);
+            select_sandpile_preset().map(Command::Sandpiles)
         },
         "2" => {
-            println!("This one dimensional cellular automata is known as Rule 110. Each row is the next stage of the row above it. If properly initialized and given sufficient space Rule 110 is capable to general computation.");
-            projects::elementary::run_elementary()
+            print_help(&projects::elementary::HELP);
+            println!("Please specify rule code less than 256");
+            select_number_lt(256).map(|code| Command::Elementary(code as u8))
         },
         "3" => {
-            println!("This is a fancy version of Conway's Game of Life that was provided as an example for the Pixels library");
-            projects::life::run_life()
+            print_help(&projects::life::HELP);
+            select_life_rule().map(|(birth, survive)| Command::Life(birth, survive))
         },
         "4" => {
-            println!("These 'Binary Totalistic Automata' count the number of live cells in a nine cell neighborhood to determine the next state.");
+            print_help(&projects::totalistic::HELP);
             println!("Please specify rule code less than 512");
-            let code = select_number_lt(512);
-            projects::totalistic::run_totalistic(code)
+            select_number_lt(512).map(Command::Totalistic)
         },
         "5" => {
-            println!("These 'Binary Outer Totalistic Automata' count the number of live cells in a nine cell neighborhood to determine the next state. However the rule is different depending on whether the center cell is active.");
-            println!("Please specify rule code less than 262144");
-            let code = select_number_lt(262144);
-            projects::outer_totalistic::run_outer_totalistic(code)
+            print_help(&projects::outer_totalistic::HELP);
+            select_outer_totalistic_code().map(Command::OuterTotalistic)
         },
         "6" => {
-            println!("This automata rotates each block 90 degree if and only if it contains exactly one live cell.");
-            println!("Press V to reverse.");
-            projects::single_rotation::run_rotor()
+            print_help(&projects::margolus::CRITTERS_HELP);
+            Some(Command::Margolus(projects::margolus::BlockRule::critters()))
+        },
+        "7" => {
+            print_help(&projects::margolus::ROTATOR_HELP);
+            Some(Command::Margolus(projects::margolus::BlockRule::single_rotation()))
+        },
+        "8" => {
+            print_help(&projects::elementary::MULTICOLOR_HELP);
+            select_rule_1d().map(Command::OneDimensional)
+        },
+        "9" => {
+            print_help(&projects::margolus::HELP);
+            select_block_rule().map(Command::Margolus)
         },
         _ => {
             println!("unknown project");
-            Ok(())
+            None
         }
     }
 }
 
+fn print_keys(keys: &[(&str, &str)]) {
+    for (key, action) in keys {
+        println!("{}: {}", key, action);
+    }
+}
+
+// The same text the help overlay shows in the window
+fn print_help(help: &Help) {
+    println!("{}", help.description);
+    print_keys(help.keys);
+}
+
+fn print_controls(options: &Options) {
+    if !options.headless {
+        println!("\n\nControls for animation:");
+        print_keys(auxiliary::runner::CONTROLS);
+    }
+}
+
 fn main() -> Result<(),Error> {
+    if std::env::args().skip(1).any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", cli::USAGE);
+        return Ok(())
+    }
+    let (command, options) = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2)
+        }
+    };
+    // A project on the command line skips the menu
+    if let Some(command) = command {
+        print_controls(&options);
+        return cli::run(command, &options)
+    }
     println!("\nWelcome to my pixel animations!\nPress 'q' to quit this screen.");
     println!("\nWARNING: Totalistic and Outer Totalistic may produce flashing lights.");
     loop {
-        println!("\n\nWhat would you like to see?\n\n1) Sandpiles\n2) Rule 110\n3) Life (not mine)\n4) Totalistic\n5) Outer Totalistic\n6) Critters\n7) Rotator");
+        println!("\n\nWhat would you like to see?\n\n1) Sandpiles\n2) Elementary\n3) Life\n4) Totalistic\n5) Outer Totalistic\n6) Critters\n7) Rotator\n8) Multicolor 1D\n9) Margolus");
         let mut val = String::new();
-        io::stdin().read_line(&mut val).expect("Failed to read line");
+        let read = io::stdin().read_line(&mut val).expect("Failed to read line");
+
+        // stdin was closed, as happens when the menu is driven by a script
+        if read == 0 {
+            break
+        }
 
         let v = val.trim();
         
@@ -76,8 +245,12 @@ fn main() -> Result<(),Error> {
             println!("\nMust input a valid command.");
             continue
         }
-        println!("\n\nControls for animation:\nC: clear screen\nP: pause\nR: randomize screen\nSPACE: frame by frame\nESC: close screen");
-        match select_animation(v) {
+        print_controls(&options);
+        let command = match select_animation(v) {
+            Some(command) => command,
+            None => continue,
+        };
+        match cli::run(command, &options) {
             Ok(_) => {
                 println!("finished animating");
             }
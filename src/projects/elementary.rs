@@ -2,244 +2,294 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
-use log::{debug, error};
-use pixels::{Error, Pixels, SurfaceTexture};
-use winit::event::{Event, VirtualKeyCode};
-use winit::event_loop::{ControlFlow, EventLoop};
+use pixels::Error;
+use winit::event::VirtualKeyCode;
 use winit_input_helper::WinitInputHelper;
 
-use crate::auxiliary::randomizer::generate_seed;
-use crate::auxiliary::window::{create_window, SCREEN_WIDTH, SCREEN_HEIGHT};
-
-
-pub fn run_elementary() -> Result<(), Error> {
-    env_logger::init();
-    let event_loop = EventLoop::new();
-    let mut input = WinitInputHelper::new();
-    let (window, p_width, p_height, mut _hidpi_factor) =
-        create_window(
-            "Rule 110", 
-            &event_loop);
-    
-    let surface_texture = SurfaceTexture::new(p_width, p_height, &window);
-
-    let mut automata = Rule110::new_random(SCREEN_WIDTH as usize, SCREEN_HEIGHT as usize);
-    let mut pixels = Pixels::new(SCREEN_WIDTH, SCREEN_HEIGHT, surface_texture)?;
-    let mut paused = false;
-
-    let mut draw_state: Option<bool> = None;
-
-    event_loop.run(move |event, _, control_flow| {
-        // The one and only event that winit_input_helper doesn't have for us...
-        if let Event::RedrawRequested(_) = event {
-            automata.draw(pixels.get_frame());
-            if pixels
-                .render()
-                .map_err(|e| error!("pixels.render() failed: {}", e))
-                .is_err()
-            {
-                *control_flow = ControlFlow::Exit;
-                return;
-            }
+use crate::auxiliary::automaton::{grid_pos, line_cells, load_cells, save_cells, Automaton, Help};
+use crate::auxiliary::history::{put_u64, take_u64};
+use crate::auxiliary::randomizer::seeded_rng;
+use crate::auxiliary::options::Options;
+use crate::auxiliary::runner::launch;
+
+
+// Colors used to draw each cell state, the first two match the original
+// black and white rendering of binary rules
+const PALETTE: [[u8; 4]; 8] = [
+    [0, 0, 0, 0xff],
+    [0xff, 0xff, 0xff, 0xff],
+    [0xff, 0x40, 0x40, 0xff],
+    [0x40, 0x80, 0xff, 0xff],
+    [0x40, 0xdd, 0x40, 0xff],
+    [0xff, 0xdd, 0, 0xff],
+    [0xdd, 0x40, 0xdd, 0xff],
+    [0, 0xdd, 0xdd, 0xff],
+];
+
+pub const MAX_COLORS: u8 = PALETTE.len() as u8;
+pub const MAX_RADIUS: usize = 3;
+
+// Split a Wolfram code written in decimal into `len` base k digits, least
+// significant first, by long division. Entry i of the result is the next
+// state for neighborhood (or sum) i. None if the code has more digits.
+//
+// Codes are kept in decimal since most rules have far too many
+// neighborhoods for their codes to fit in any integer type.
+fn code_to_table(code: &str, colors: u8, len: usize) -> Option<Vec<u8>> {
+    let colors = colors as u32;
+    let mut decimal: Vec<u32> = code.bytes().map(|b| (b - b'0') as u32).collect();
+    let mut table = vec![0; len];
+    for t in table.iter_mut() {
+        let mut remainder = 0;
+        for d in decimal.iter_mut() {
+            let n = remainder * 10 + *d;
+            *d = n / colors;
+            remainder = n % colors;
+        }
+        *t = remainder as u8;
+        let zeros = decimal.iter().take_while(|&&d| d == 0).count();
+        decimal.drain(..zeros);
+        if decimal.is_empty() {
+            return Some(table);
         }
+    }
+    None
+}
 
-        // For everything else, for let winit_input_helper collect events to build its state.
-        // It returns `true` when it is time to update our game state and request a redraw.
-        if input.update(&event) {
-            // Close events
-            if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
-                *control_flow = ControlFlow::Exit;
-                return;
-            }
-            if input.key_pressed(VirtualKeyCode::P) {
-                paused = !paused;
-                match paused {
-                    true => println!("paused"),
-                    false => println!("unpaused"),
-                }
-            }
-            if input.key_pressed(VirtualKeyCode::Space) {
-                // Space is frame-step, so ensure we're paused
-                println!("frame advanced");
-                paused = true;
-            }
-            if input.key_pressed(VirtualKeyCode::R) {
-                println!("reset with random coditions");
-                automata.clear();
-                automata.randomize();
-            }
-            if input.key_pressed(VirtualKeyCode::C) {
-                println!("screen cleared and active line reset");
-                automata.clear();
-                automata.active_line = 1;
-            }
-            if input.key_pressed(VirtualKeyCode::N) {
-                println!("active line reset");
-                automata.active_line = 1;
-            }
-            // Handle mouse. This is a bit involved since support some simple
-            // line drawing (mostly because it makes nice looking patterns).
-            let (mouse_cell, mouse_prev_cell) = input
-                .mouse()
-                .map(|(mx, my)| {
-                    let (dx, dy) = input.mouse_diff();
-                    let prev_x = mx - dx;
-                    let prev_y = my - dy;
-
-                    let (mx_i, my_i) = pixels
-                        .window_pos_to_pixel((mx, my))
-                        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-
-                    let (px_i, py_i) = pixels
-                        .window_pos_to_pixel((prev_x, prev_y))
-                        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-
-                    (
-                        (mx_i as isize, my_i as isize),
-                        (px_i as isize, py_i as isize),
-                    )
-                })
-                .unwrap_or_default();
-
-            if input.mouse_pressed(0) {
-                debug!("Mouse click at {:?}", mouse_cell);
-                draw_state = Some(automata.toggle(mouse_cell.0, mouse_cell.1));
-            } else if let Some(draw_alive) = draw_state {
-                let release = input.mouse_released(0);
-                let held = input.mouse_held(0);
-                debug!("Draw at {:?} => {:?}", mouse_prev_cell, mouse_cell);
-                debug!("Mouse held {:?}, release {:?}", held, release);
-                // If they either released (finishing the drawing) or are still
-                // in the middle of drawing, keep going.
-                if release || held {
-                    debug!("Draw line of {:?}", draw_alive);
-                    automata.set_line(
-                        mouse_prev_cell.0,
-                        mouse_prev_cell.1,
-                        mouse_cell.0,
-                        mouse_cell.1,
-                        draw_alive,
-                    );
-                }
-                // If they let go or are otherwise not clicking anymore, stop drawing.
-                if release || !held {
-                    debug!("Draw end");
-                    draw_state = None;
-                }
-            }
-            // Adjust high DPI factor
-            if let Some(factor) = input.scale_factor_changed() {
-                _hidpi_factor = factor;
-            }
-            // Resize the window
-            if let Some(size) = input.window_resized() {
-                pixels.resize_surface(size.width, size.height);
+// The decimal number one more or one less than `code`, which is above zero
+// when going down
+fn step_decimal(code: &str, up: bool) -> String {
+    let mut digits = code.as_bytes().to_vec();
+    for d in digits.iter_mut().rev() {
+        match (up, *d) {
+            (true, b'9') => *d = b'0',
+            (false, b'0') => *d = b'9',
+            (true, _) => {
+                *d += 1;
+                break;
             }
-            if !paused || input.key_pressed(VirtualKeyCode::Space) {
-                automata.update();
+            (false, _) => {
+                *d -= 1;
+                break;
             }
-            window.request_redraw();
         }
-    });
+    }
+    if digits[0] == b'0' {
+        if up {
+            digits.insert(0, b'1');
+        } else if digits.len() > 1 {
+            digits.remove(0);
+        }
+    }
+    String::from_utf8(digits).unwrap()
 }
 
-
-const INITIAL_FILL: f32 = 0.5;
-
-#[derive(Clone, Copy, Debug, Default)]
-struct Cell {
-    alive: bool
+/// A one dimensional rule with `colors` states and a neighborhood of
+/// `radius` cells on each side, numbered using Wolfram's method.
+///
+/// A general rule has a table entry for every neighborhood, read left to right
+/// as the digits of a base `colors` number. A totalistic rule only looks at
+/// the sum of the states in the neighborhood.
+#[derive(Clone, Debug)]
+pub struct Rule1D {
+    colors: u8,
+    radius: usize,
+    totalistic: bool,
+    // In decimal without leading zeros
+    code: String,
+    table: Vec<u8>,
 }
 
-impl Cell {
-    fn new(alive: bool) -> Self {
-        Self { alive }
-    }
-
-    fn next_state(self, neibs: (bool,bool,bool)) -> Self {
-        let alive = match neibs {
-            (true, true, true)    => false,
-            (true, true, false)   => true,
-            (true, false, true)   => true,
-            (true, false, false)  => false,
-            (false, true, true)   => true,
-            (false, true, false)  => true,
-            (false, false, true)  => true,
-            (false, false, false) => false,
+impl Rule1D {
+    /// The rule with a code given in decimal, which can be any length.
+    pub fn new(colors: u8, radius: usize, totalistic: bool, code: &str) -> Result<Self, String> {
+        if !(2..=MAX_COLORS).contains(&colors) {
+            return Err(format!("number of colors must be between 2 and {}", MAX_COLORS));
+        }
+        if !(1..=MAX_RADIUS).contains(&radius) {
+            return Err(format!("radius must be between 1 and {}", MAX_RADIUS));
+        }
+        let width = 2 * radius + 1;
+        let len = if totalistic {
+            width * (colors as usize - 1) + 1
+        } else {
+            (colors as usize).pow(width as u32)
+        };
+        let code = code.trim();
+        if code.is_empty() || !code.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("invalid rule code '{}'", code));
+        }
+        let code = match code.trim_start_matches('0') {
+            "" => "0",
+            code => code,
         };
-        Self::new(alive)
+        let mut rule = Self {
+            colors,
+            radius,
+            totalistic,
+            code: code.to_string(),
+            table: Vec::new(),
+        };
+        rule.table = code_to_table(code, colors, len)
+            .ok_or_else(|| format!("rule code must be at most {}", rule.max_code()))?;
+        Ok(rule)
+    }
+
+    /// One of the 256 binary nearest neighbor rules.
+    pub fn elementary(code: u8) -> Self {
+        Self::new(2, 1, false, &code.to_string()).unwrap()
+    }
+
+    /// The largest code, k^len - 1, written out when it isn't too long.
+    pub fn max_code(&self) -> String {
+        let len = self.table.len();
+        match u32::try_from(len).ok().and_then(|len| (self.colors as u128).checked_pow(len)) {
+            Some(n) => (n - 1).to_string(),
+            None => format!("{}^{} - 1", self.colors, len),
+        }
     }
 
-    fn toggle(&mut self) {
-        self.alive = !self.alive
+    /// The rule with the code one above or below this one, wrapping around
+    /// at the ends of the valid range. The table is counted up or down along
+    /// with the code. None when going below 0 to a largest code too long to
+    /// write out.
+    fn step_code(&self, up: bool) -> Option<Self> {
+        let (from, to) = if up { (self.colors - 1, 0) } else { (0, self.colors - 1) };
+        if self.table.iter().all(|&t| t == from) {
+            let code = if up { "0".to_string() } else { self.max_code() };
+            return Self::new(self.colors, self.radius, self.totalistic, &code).ok();
+        }
+        let mut table = self.table.clone();
+        for t in table.iter_mut() {
+            if *t == from {
+                *t = to;
+            } else {
+                *t = if up { *t + 1 } else { *t - 1 };
+                break;
+            }
+        }
+        Some(Self {
+            code: step_decimal(&self.code, up),
+            table,
+            ..self.clone()
+        })
     }
 
-    fn set_alive(&mut self) {
-        self.alive = true
+    fn next_state(&self, neibs: &[u8]) -> u8 {
+        let idx = if self.totalistic {
+            neibs.iter().map(|&s| s as usize).sum()
+        } else {
+            neibs.iter().fold(0, |acc, &s| acc * self.colors as usize + s as usize)
+        };
+        self.table[idx]
     }
 
-    fn _set_dead(&mut self) {
-        self.alive = false
+    pub fn name(&self) -> String {
+        if self.colors == 2 && self.radius == 1 && !self.totalistic {
+            format!("Elementary Rule {}", self.code)
+        } else if self.totalistic {
+            format!("k={} r={} Totalistic Code {}", self.colors, self.radius, self.code)
+        } else {
+            format!("k={} r={} Rule {}", self.colors, self.radius, self.code)
+        }
     }
+}
 
+const RULE_KEYS: &[(&str, &str)] = &[
+    ("[", "previous rule"),
+    ("]", "next rule"),
+    ("N", "start again below the first row"),
+];
+
+pub const HELP: Help = Help {
+    description: "These one dimensional 'Elementary Cellular Automata' use the three cells above each cell to determine its state. Each row is the next stage of the row above it. Rule 110 is famously capable of general computation when properly initialized and given sufficient space.",
+    keys: RULE_KEYS,
+};
+
+pub const MULTICOLOR_HELP: Help = Help {
+    description: "These one dimensional automata generalize the Elementary rules to more colors and wider neighborhoods. Totalistic rules only depend on the sum of the states above each cell.",
+    keys: RULE_KEYS,
+};
+
+pub fn run_elementary(rule: u8, options: &Options) -> Result<(), Error> {
+    run_one_dimensional(Rule1D::elementary(rule), options)
+}
+
+pub fn run_one_dimensional(rule: Rule1D, options: &Options) -> Result<(), Error> {
+    // Tables of the wider rules run to millions of entries
+    if rule.table.len() <= 64 {
+        println!("{} parsed as: {:?}",rule.name(),rule.table);
+    }
+    let title = rule.name();
+    let automata = Elementary::new_empty(options.width, options.height, rule);
+    launch(automata, &title, options)
+}
+
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Cell {
+    state: u8
+}
+
+impl Cell {
+    fn new(state: u8) -> Self {
+        Self { state }
+    }
 }
 
 #[derive(Clone, Debug)]
-struct Rule110 {
+struct Elementary {
     cells: Vec<Cell>,
     width: usize,
     height: usize,
     active_line: usize,
+    rule: Rule1D,
+    // state drawn with the mouse, set by the most recent click
+    paint: u8,
+    // reused buffer for the states around the current cell
+    neibs: Vec<u8>,
 }
 
-impl Rule110 {
-    fn new_empty(width: usize, height: usize) -> Self {
+impl Elementary {
+    fn new_empty(width: usize, height: usize, rule: Rule1D) -> Self {
         assert!(width != 0 && height != 0);
         let size = width.checked_mul(height).expect("too big");
         Self {
             cells: vec![Cell::default(); size],
             width,
             height,
-            active_line: 1,
+            // A single row has nothing below the seed to fill in
+            active_line: 1 % height,
+            neibs: Vec::with_capacity(2 * rule.radius + 1),
+            rule,
+            paint: 1,
         }
     }
 
-    fn new_random(width: usize, height: usize) -> Self {
-        let mut result = Self::new_empty(width, height);
-        result.randomize();
-        result
+    fn set_rule(&mut self, rule: Rule1D) {
+        self.rule = rule;
+        println!("{}", self.rule.name());
     }
 
-    fn randomize(&mut self) {
-        // Randomize the first row
-        let mut rng: randomize::PCG32 = generate_seed().into();
-        for (n, c) in self.cells.iter_mut().enumerate() {
-            if n as u32 > SCREEN_WIDTH {
-                break
-            }
-            let alive = randomize::f32_half_open_right(rng.next_u32()) > INITIAL_FILL;
-            *c = Cell::new(alive);
+    // Fill `self.neibs` with the row above (x, y), wrapping around the sides
+    fn neibs(&mut self, x: usize, y: usize) {
+        let ym1 = if y == 0 {
+            self.height - 1
+        } else {
+            y - 1
+        };
+        let r = self.rule.radius;
+        self.neibs.clear();
+        for dx in 0..2 * r + 1 {
+            let nx = (x + self.width * r + dx - r) % self.width;
+            self.neibs.push(self.cells[nx + ym1 * self.width].state);
         }
     }
+}
 
-    fn neibs(&self, x: usize, y: usize) -> (bool,bool,bool) {
-        let (xm1, xp1) = if x == 0 {
-            (self.width - 1, x + 1)
-        } else if x == self.width - 1 {
-            (x - 1, 0)
-        } else {
-            (x - 1, x + 1)
-        };
-        let (ym1, _) = if y == 0 {
-            (self.height - 1, y + 1)
-        } else if y == self.height - 1 {
-            (y - 1, 0)
-        } else {
-            (y - 1, y + 1)
-        };
-        (self.cells[xm1 + ym1 * self.width].alive,
-            self.cells[x + ym1 * self.width].alive,
-            self.cells[xp1 + ym1 * self.width].alive)
+impl Automaton for Elementary {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
     }
 
     fn update(&mut self) {
@@ -249,11 +299,10 @@ impl Rule110 {
             // Do nothing
         } else {
             for x in 0..self.width {
-                let neibs = self.neibs(x, y);
+                self.neibs(x, y);
                 let idx = x + y * self.width;
-                let next = self.cells[idx].next_state(neibs);
-                // Write into `self.scratch_cells`, since we're still reading from `self.cells`
-                self.cells[idx] = next;
+                // Only the row above is read so we can write in place
+                self.cells[idx] = Cell::new(self.rule.next_state(&self.neibs));
             }
             self.active_line += 1;
             if self.active_line >= self.height {
@@ -263,59 +312,122 @@ impl Rule110 {
         }
     }
 
+    fn draw(&self, screen: &mut [u8]) {
+        debug_assert_eq!(screen.len(), 4 * self.cells.len());
+        for (c, pix) in self.cells.iter().zip(screen.chunks_exact_mut(4)) {
+            pix.copy_from_slice(&PALETTE[c.state as usize]);
+        }
+    }
+
+    // Clicking cycles through the states and dragging paints the new state
+    fn toggle(&mut self, x: isize, y: isize) -> bool {
+        match grid_pos(x, y, self.size()) {
+            Some((x, y)) => {
+                let i = x + y * self.width;
+                let next = (self.cells[i].state + 1) % self.rule.colors;
+                self.cells[i] = Cell::new(next);
+                self.paint = next;
+                next != 0
+            }
+            None => false,
+        }
+    }
+
+    fn set_line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize, _alive: bool) {
+        for (x, y) in line_cells((x0, y0), (x1, y1), self.size()) {
+            self.cells[x + y * self.width] = Cell::new(self.paint);
+        }
+    }
+
+    fn cell(&self, x: usize, y: usize) -> u32 {
+        self.cells[x + y * self.width].state as u32
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, value: u32) {
+        self.cells[x + y * self.width] = Cell::new((value % self.rule.colors as u32) as u8);
+    }
+
+    fn fill_value(&self) -> u32 {
+        self.paint as u32
+    }
+
+    fn random_cell(&self, rng: &mut randomize::PCG32) -> u32 {
+        rng.next_u32() % self.rule.colors as u32
+    }
+
+    fn set_size(&mut self, width: usize, height: usize) {
+        self.cells = vec![Cell::default(); width * height];
+        self.width = width;
+        self.height = height;
+        // Rows that were cut off can't be continued
+        if self.active_line >= height {
+            self.active_line = 0;
+        }
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        let mut state = save_cells(self);
+        put_u64(&mut state, self.active_line as u64);
+        state
+    }
+
+    fn load_state(&mut self, mut state: &[u8]) {
+        load_cells(self, &mut state);
+        self.active_line = take_u64(&mut state) as usize;
+    }
+
     fn clear(&mut self) {
-        self.active_line = 1;
+        self.active_line = 1 % self.height;
         for c in self.cells.iter_mut() {
             *c = Cell::default();
         }
     }
 
-    fn toggle(&mut self, x: isize, y: isize) -> bool {
-        if let Some(i) = self.grid_idx(x, y) {
-            let was_alive = self.cells[i].alive;
-            self.cells[i].toggle();
-            !was_alive
-        } else {
-            false
-        }
+    fn population(&self) -> u64 {
+        self.cells.iter().filter(|c| c.state != 0).count() as u64
     }
 
-    fn draw(&self, screen: &mut [u8]) {
-        debug_assert_eq!(screen.len(), 4 * self.cells.len());
-        for (c, pix) in self.cells.iter().zip(screen.chunks_exact_mut(4)) {
-            let color = if c.alive {
-                [0xff, 0xff, 0xff, 0xff]
-            } else {
-                [0, 0, 0, 0xff]
-            };
-            pix.copy_from_slice(&color);
+    fn rule_name(&self) -> String {
+        self.rule.name()
+    }
+
+    fn title(&self) -> Option<String> {
+        Some(self.rule.name())
+    }
+
+    fn randomize(&mut self, seed: u64) {
+        self.clear();
+        // Randomize the first row
+        let mut rng = seeded_rng(seed);
+        for x in 0..self.width {
+            let state = self.random_cell(&mut rng);
+            self.set_cell(x, 0, state);
         }
     }
 
-    fn set_line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize, _alive: bool) {
-        // probably should do sutherland-hodgeman if this were more serious.
-        // instead just clamp the start pos, and draw until moving towards the
-        // end pos takes us out of bounds.
-        let x0 = x0.max(0).min(self.width as isize);
-        let y0 = y0.max(0).min(self.height as isize);
-        for (x, y) in line_drawing::Bresenham::new((x0, y0), (x1, y1)) {
-            if let Some(i) = self.grid_idx(x, y) {
-                self.cells[i].set_alive();
-            } else {
-                break;
-            }
+    fn starts_random(&self) -> bool {
+        true
+    }
+
+    fn help(&self) -> &'static Help {
+        match (self.rule.colors, self.rule.radius, self.rule.totalistic) {
+            (2, 1, false) => &HELP,
+            _ => &MULTICOLOR_HELP,
         }
     }
 
-    fn grid_idx<I: std::convert::TryInto<usize>>(&self, x: I, y: I) -> Option<usize> {
-        if let (Ok(x), Ok(y)) = (x.try_into(), y.try_into()) {
-            if x < self.width && y < self.height {
-                Some(x + y * self.width)
-            } else {
-                None
+    fn handle_keys(&mut self, input: &WinitInputHelper) {
+        if input.key_pressed(VirtualKeyCode::N) {
+            println!("active line reset");
+            self.active_line = 1 % self.height;
+        }
+        for (key, up) in [(VirtualKeyCode::RBracket, true), (VirtualKeyCode::LBracket, false)] {
+            if input.key_pressed(key) {
+                match self.rule.step_code(up) {
+                    Some(rule) => self.set_rule(rule),
+                    None => println!("the largest code is too long to wrap around to"),
+                }
             }
-        } else {
-            None
         }
     }
 }
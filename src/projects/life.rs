@@ -3,134 +3,39 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
-use log::{debug, error};
-use pixels::{Error, Pixels, SurfaceTexture};
-use winit::event::{Event, VirtualKeyCode};
-use winit::event_loop::{ControlFlow, EventLoop};
+use pixels::Error;
 use winit_input_helper::WinitInputHelper;
 
-use crate::auxiliary::randomizer::generate_seed;
-use crate::auxiliary::window::{create_window, SCREEN_WIDTH, SCREEN_HEIGHT};
-
-
-pub fn run_life() -> Result<(), Error> {
-    env_logger::init();
-    let event_loop = EventLoop::new();
-    let mut input = WinitInputHelper::new();
-    let (window, p_width, p_height, mut _hidpi_factor) =
-        create_window(
-            "Conway's Game of Life", 
-            &event_loop);
-
-    let surface_texture = SurfaceTexture::new(p_width, p_height, &window);
-
-    let mut life = ConwayGrid::new_random(SCREEN_WIDTH as usize, SCREEN_HEIGHT as usize);
-    let mut pixels = Pixels::new(SCREEN_WIDTH, SCREEN_HEIGHT, surface_texture)?;
-    let mut paused = false;
-
-    let mut draw_state: Option<bool> = None;
-
-    event_loop.run(move |event, _, control_flow| {
-        // The one and only event that winit_input_helper doesn't have for us...
-        if let Event::RedrawRequested(_) = event {
-            life.draw(pixels.get_frame());
-            if pixels
-                .render()
-                .map_err(|e| error!("pixels.render() failed: {}", e))
-                .is_err()
-            {
-                *control_flow = ControlFlow::Exit;
-                return;
-            }
-        }
+use crate::auxiliary::automaton::{load_cells, resize_cells, save_cells, Automaton, Help};
+use crate::auxiliary::boundary::Boundary;
+use crate::auxiliary::history::take_bytes;
+use crate::auxiliary::randomizer::seeded_rng;
+use crate::auxiliary::options::Options;
+use crate::auxiliary::rulestring::{parse_rule, rule_to_string, CONWAY_RULE};
+use crate::auxiliary::runner::launch;
 
-        // For everything else, for let winit_input_helper collect events to build its state.
-        // It returns `true` when it is time to update our game state and request a redraw.
-        if input.update(&event) {
-            // Close events
-            if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
-                *control_flow = ControlFlow::Exit;
-                return;
-            }
-            if input.key_pressed(VirtualKeyCode::P) {
-                paused = !paused;
-            }
-            if input.key_pressed(VirtualKeyCode::Space) {
-                // Space is frame-step, so ensure we're paused
-                paused = true;
-            }
-            if input.key_pressed(VirtualKeyCode::R) {
-                life.randomize();
-            }
-            // Handle mouse. This is a bit involved since support some simple
-            // line drawing (mostly because it makes nice looking patterns).
-            let (mouse_cell, mouse_prev_cell) = input
-                .mouse()
-                .map(|(mx, my)| {
-                    let (dx, dy) = input.mouse_diff();
-                    let prev_x = mx - dx;
-                    let prev_y = my - dy;
-
-                    let (mx_i, my_i) = pixels
-                        .window_pos_to_pixel((mx, my))
-                        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-
-                    let (px_i, py_i) = pixels
-                        .window_pos_to_pixel((prev_x, prev_y))
-                        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-
-                    (
-                        (mx_i as isize, my_i as isize),
-                        (px_i as isize, py_i as isize),
-                    )
-                })
-                .unwrap_or_default();
-
-            if input.mouse_pressed(0) {
-                debug!("Mouse click at {:?}", mouse_cell);
-                draw_state = Some(life.toggle(mouse_cell.0, mouse_cell.1));
-            } else if let Some(draw_alive) = draw_state {
-                let release = input.mouse_released(0);
-                let held = input.mouse_held(0);
-                debug!("Draw at {:?} => {:?}", mouse_prev_cell, mouse_cell);
-                debug!("Mouse held {:?}, release {:?}", held, release);
-                // If they either released (finishing the drawing) or are still
-                // in the middle of drawing, keep going.
-                if release || held {
-                    debug!("Draw line of {:?}", draw_alive);
-                    life.set_line(
-                        mouse_prev_cell.0,
-                        mouse_prev_cell.1,
-                        mouse_cell.0,
-                        mouse_cell.1,
-                        draw_alive,
-                    );
-                }
-                // If they let go or are otherwise not clicking anymore, stop drawing.
-                if release || !held {
-                    debug!("Draw end");
-                    draw_state = None;
-                }
-            }
-            // Adjust high DPI factor
-            if let Some(factor) = input.scale_factor_changed() {
-                _hidpi_factor = factor;
-            }
-            // Resize the window
-            if let Some(size) = input.window_resized() {
-                pixels.resize_surface(size.width, size.height);
-            }
-            if !paused || input.key_pressed(VirtualKeyCode::Space) {
-                life.update();
-            }
-            window.request_redraw();
-        }
-    });
+
+pub const HELP: Help = Help {
+    description: "This is a fancy version of Conway's Game of Life that was provided as an example for the Pixels library. It can also run any other Life-like rule.",
+    keys: &[("B", "change boundary")],
+};
+
+pub fn run_life(birth: [bool;9], survive: [bool;9], options: &Options) -> Result<(), Error> {
+    println!("Rule parsed as {}", rule_to_string(birth, survive));
+    let life = ConwayGrid::new_empty(options.width, options.height, birth, survive, options.boundary);
+    launch(life, &title(birth, survive), options)
+}
+
+fn title(birth: [bool;9], survive: [bool;9]) -> String {
+    let rule = rule_to_string(birth, survive);
+    if rule == CONWAY_RULE {
+        "Conway's Game of Life".to_string()
+    } else {
+        format!("Life {}", rule)
+    }
 }
 
 
-const BIRTH_RULE: [bool; 9] = [false, false, false, true, false, false, false, false, false];
-const SURVIVE_RULE: [bool; 9] = [false, false, true, true, false, false, false, false, false];
 const INITIAL_FILL: f32 = 0.3;
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -149,11 +54,11 @@ impl Cell {
     }
 
     #[must_use]
-    fn update_neibs(self, n: usize) -> Self {
+    fn update_neibs(self, n: usize, birth: [bool;9], survive: [bool;9]) -> Self {
         let next_alive = if self.alive {
-            SURVIVE_RULE[n]
+            survive[n]
         } else {
-            BIRTH_RULE[n]
+            birth[n]
         };
         self.next_state(next_alive)
     }
@@ -175,7 +80,7 @@ impl Cell {
 
     fn cool_off(&mut self, decay: f32) {
         if !self.alive {
-            let heat = (self.heat as f32 * decay).min(255.0).max(0.0);
+            let heat = (self.heat as f32 * decay).clamp(0.0, 255.0);
             assert!(heat.is_finite());
             self.heat = heat as u8;
         }
@@ -191,10 +96,13 @@ struct ConwayGrid {
     // `cells` and write to `scratch_cells`, then swap. Otherwise it's not in
     // use, and `cells` should be updated directly.
     scratch_cells: Vec<Cell>,
+    birth: [bool;9],
+    survive: [bool;9],
+    boundary: Boundary,
 }
 
 impl ConwayGrid {
-    fn new_empty(width: usize, height: usize) -> Self {
+    fn new_empty(width: usize, height: usize, birth: [bool;9], survive: [bool;9], boundary: Boundary) -> Self {
         assert!(width != 0 && height != 0);
         let size = width.checked_mul(height).expect("too big");
         Self {
@@ -202,55 +110,21 @@ impl ConwayGrid {
             scratch_cells: vec![Cell::default(); size],
             width,
             height,
+            birth,
+            survive,
+            boundary,
         }
     }
 
-    fn new_random(width: usize, height: usize) -> Self {
-        let mut result = Self::new_empty(width, height);
-        result.randomize();
-        result
-    }
-
-    fn randomize(&mut self) {
-        let mut rng: randomize::PCG32 = generate_seed().into();
-        for c in self.cells.iter_mut() {
-            let alive = randomize::f32_half_open_right(rng.next_u32()) > INITIAL_FILL;
-            *c = Cell::new(alive);
-        }
-        // run a few simulation iterations for aesthetics (If we don't, the
-        // noise is ugly)
-        for _ in 0..3 {
-            self.update();
-        }
-        // Smooth out noise in the heatmap that would remain for a while
-        for c in self.cells.iter_mut() {
-            c.cool_off(0.4);
-        }
+    fn count_neibs(&self, x: usize, y: usize) -> usize {
+        self.boundary
+            .count_neibs(x, y, self.width, self.height, |i| self.cells[i].alive)
     }
+}
 
-    fn count_neibs(&self, x: usize, y: usize) -> usize {
-        let (xm1, xp1) = if x == 0 {
-            (self.width - 1, x + 1)
-        } else if x == self.width - 1 {
-            (x - 1, 0)
-        } else {
-            (x - 1, x + 1)
-        };
-        let (ym1, yp1) = if y == 0 {
-            (self.height - 1, y + 1)
-        } else if y == self.height - 1 {
-            (y - 1, 0)
-        } else {
-            (y - 1, y + 1)
-        };
-        self.cells[xm1 + ym1 * self.width].alive as usize
-            + self.cells[x + ym1 * self.width].alive as usize
-            + self.cells[xp1 + ym1 * self.width].alive as usize
-            + self.cells[xm1 + y * self.width].alive as usize
-            + self.cells[xp1 + y * self.width].alive as usize
-            + self.cells[xm1 + yp1 * self.width].alive as usize
-            + self.cells[x + yp1 * self.width].alive as usize
-            + self.cells[xp1 + yp1 * self.width].alive as usize
+impl Automaton for ConwayGrid {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
     }
 
     fn update(&mut self) {
@@ -258,7 +132,7 @@ impl ConwayGrid {
             for x in 0..self.width {
                 let neibs = self.count_neibs(x, y);
                 let idx = x + y * self.width;
-                let next = self.cells[idx].update_neibs(neibs);
+                let next = self.cells[idx].update_neibs(neibs, self.birth, self.survive);
                 // Write into scratch_cells, since we're still reading from `self.cells`
                 self.scratch_cells[idx] = next;
             }
@@ -266,16 +140,6 @@ impl ConwayGrid {
         std::mem::swap(&mut self.scratch_cells, &mut self.cells);
     }
 
-    fn toggle(&mut self, x: isize, y: isize) -> bool {
-        if let Some(i) = self.grid_idx(x, y) {
-            let was_alive = self.cells[i].alive;
-            self.cells[i].set_alive(!was_alive);
-            !was_alive
-        } else {
-            false
-        }
-    }
-
     fn draw(&self, screen: &mut [u8]) {
         debug_assert_eq!(screen.len(), 4 * self.cells.len());
         for (c, pix) in self.cells.iter().zip(screen.chunks_exact_mut(4)) {
@@ -288,30 +152,122 @@ impl ConwayGrid {
         }
     }
 
-    fn set_line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize, alive: bool) {
-        // probably should do sutherland-hodgeman if this were more serious.
-        // instead just clamp the start pos, and draw until moving towards the
-        // end pos takes us out of bounds.
-        let x0 = x0.max(0).min(self.width as isize);
-        let y0 = y0.max(0).min(self.height as isize);
-        for (x, y) in line_drawing::Bresenham::new((x0, y0), (x1, y1)) {
-            if let Some(i) = self.grid_idx(x, y) {
-                self.cells[i].set_alive(alive);
-            } else {
-                break;
-            }
+    fn cell(&self, x: usize, y: usize) -> u32 {
+        self.cells[x + y * self.width].alive as u32
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, value: u32) {
+        self.cells[x + y * self.width].set_alive(value != 0);
+    }
+
+    fn random_cell(&self, rng: &mut randomize::PCG32) -> u32 {
+        (randomize::f32_half_open_right(rng.next_u32()) > INITIAL_FILL) as u32
+    }
+
+    fn set_size(&mut self, width: usize, height: usize) {
+        self.cells = vec![Cell::default(); width * height];
+        self.scratch_cells = vec![Cell::default(); width * height];
+        self.width = width;
+        self.height = height;
+    }
+    fn resize(&mut self, width: usize, height: usize) {
+        // Cells are copied whole so they keep their heat
+        let cells = resize_cells(&self.cells, self.width, self.height, width, height, Cell::default());
+        self.set_size(width, height);
+        self.cells = cells;
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        let mut state = save_cells(self);
+        state.extend(self.cells.iter().map(|c| c.heat));
+        state
+    }
+
+    fn load_state(&mut self, mut state: &[u8]) {
+        load_cells(self, &mut state);
+        let heat = take_bytes(&mut state, self.cells.len());
+        for (c, &h) in self.cells.iter_mut().zip(heat) {
+            c.heat = h;
         }
     }
 
-    fn grid_idx<I: std::convert::TryInto<usize>>(&self, x: I, y: I) -> Option<usize> {
-        if let (Ok(x), Ok(y)) = (x.try_into(), y.try_into()) {
-            if x < self.width && y < self.height {
-                Some(x + y * self.width)
-            } else {
-                None
-            }
-        } else {
-            None
+    fn clear(&mut self) {
+        for c in self.cells.iter_mut() {
+            *c = Cell::default();
         }
     }
+
+    fn population(&self) -> u64 {
+        self.cells.iter().filter(|c| c.alive).count() as u64
+    }
+
+    fn rule_name(&self) -> String {
+        rule_to_string(self.birth, self.survive)
+    }
+
+    fn title(&self) -> Option<String> {
+        Some(title(self.birth, self.survive))
+    }
+
+    fn rulestring(&self) -> Option<String> {
+        Some(self.rule_name())
+    }
+
+    fn set_rulestring(&mut self, rule: &str) -> Result<(), String> {
+        (self.birth, self.survive) = parse_rule(rule)?;
+        Ok(())
+    }
+
+    fn randomize(&mut self, seed: u64) {
+        let mut rng = seeded_rng(seed);
+        for i in 0..self.cells.len() {
+            let alive = self.random_cell(&mut rng) != 0;
+            self.cells[i] = Cell::new(alive);
+        }
+        // run a few simulation iterations for aesthetics (If we don't, the
+        // noise is ugly)
+        for _ in 0..3 {
+            self.update();
+        }
+        // Smooth out noise in the heatmap that would remain for a while
+        for c in self.cells.iter_mut() {
+            c.cool_off(0.4);
+        }
+    }
+
+    fn starts_random(&self) -> bool {
+        true
+    }
+
+    fn help(&self) -> &'static Help {
+        &HELP
+    }
+
+    fn handle_keys(&mut self, input: &WinitInputHelper) {
+        self.boundary.handle_keys(input);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary::patterns::{parse_rle, place};
+
+    #[test]
+    fn saved_state_brings_back_the_rule_a_pattern_replaced() {
+        let (birth, survive) = parse_rule(CONWAY_RULE).unwrap();
+        let mut life = ConwayGrid::new_empty(8, 8, birth, survive, Boundary::Torus);
+        life.set_cell(1, 2, 1);
+        let before = life.save_state();
+
+        let file = parse_rle("x = 3, y = 1, rule = B36/S23\n3o!", (8, 8)).unwrap();
+        place(&mut life, &file);
+        assert_eq!(life.rulestring().as_deref(), Some("B36/S23"));
+        assert_eq!(life.title().as_deref(), Some("Life B36/S23"));
+
+        life.load_state(&before);
+        assert_eq!(life.rulestring().as_deref(), Some(CONWAY_RULE));
+        assert_eq!(life.title().as_deref(), Some("Conway's Game of Life"));
+        assert_eq!(life.population(), 1);
+        assert_eq!(life.cell(1, 2), 1);
+    }
 }
@@ -0,0 +1,371 @@
+//https://github.com/parasyte/pixels/tree/c2454b01abc11c007d4b9de8525195af942fef0d/examples/conway
+
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use pixels::Error;
+use winit::event::VirtualKeyCode;
+use winit_input_helper::WinitInputHelper;
+
+use crate::auxiliary::automaton::{load_cells, save_cells, Automaton, Help};
+use crate::auxiliary::boundary::{Boundary, Neighbor};
+use crate::auxiliary::history::take_bytes;
+use crate::auxiliary::options::Options;
+use crate::auxiliary::runner::launch;
+
+// Margolus neighborhood automata
+// https://en.wikipedia.org/wiki/Block_cellular_automaton
+//
+// The grid is cut into 2x2 blocks which are each replaced according to a
+// lookup table. On alternate steps the blocks are shifted one cell down and to
+// the right. A block is numbered by reading its cells clockwise from the top
+// left as the bits of a four bit number, so the top left cell alone is 1, the
+// top right alone is 2, the bottom right alone is 4 and the bottom left alone
+// is 8.
+
+
+// Rotate 90 degrees iff the block contains exactly one live cell
+const SINGLE_ROTATION: [u8;16] = [0, 8, 1, 3, 2, 5, 6, 7, 4, 9, 10, 11, 12, 13, 14, 15];
+
+// Critters inverts every block except those with exactly two live cells and
+// also rotates blocks with three live cells 180 degrees. Applied as written
+// empty space flashes between all dead and all alive so instead the grid is
+// complemented after each even step and before each odd step, which gives
+// identical results every two steps.
+const CRITTERS_EVEN: [u8;16] = [0, 1, 2, 12, 4, 10, 9, 13, 8, 6, 5, 14, 3, 7, 11, 15];
+const CRITTERS_ODD: [u8;16] = [0, 4, 8, 12, 1, 10, 9, 7, 2, 6, 5, 11, 3, 13, 14, 15];
+
+// Invert blocks where every cell is the same, leave the rest alone
+const TRON: [u8;16] = [15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0];
+
+// A lone cell moves to the opposite corner and two diagonal cells (a collision)
+// bounce off onto the other diagonal
+const BILLIARD_BALL: [u8;16] = [0, 4, 8, 3, 1, 10, 6, 7, 2, 9, 5, 11, 12, 13, 14, 15];
+
+const BLOCK_KEYS: &[(&str, &str)] = &[
+    ("V", "run backwards, for reversible rules"),
+    ("B", "change boundary"),
+];
+
+pub const HELP: Help = Help {
+    description: "Margolus automata split the grid into 2x2 blocks that alternate position each step. Each block is replaced using a table of 16 entries, where a block is numbered by reading its cells clockwise from the top left as binary digits, lowest first.",
+    keys: BLOCK_KEYS,
+};
+
+pub const CRITTERS_HELP: Help = Help {
+    description: "Critters is a reversible automata on 2x2 blocks that alternate position each step. Blocks with exactly two live cells are unchanged, all others are inverted and blocks with three live cells are also rotated 180 degrees.",
+    keys: BLOCK_KEYS,
+};
+
+pub const ROTATOR_HELP: Help = Help {
+    description: "This automata rotates each block 90 degree if and only if it contains exactly one live cell.",
+    keys: BLOCK_KEYS,
+};
+
+/// The lookup tables that define a Margolus automaton, one for blocks in the
+/// even (aligned) phase and one for the odd (shifted) phase.
+#[derive(Clone, Debug)]
+pub struct BlockRule {
+    name: String,
+    even: [u8;16],
+    odd: [u8;16],
+}
+
+// The inverse permutation, if the table is a permutation at all
+fn invert_table(table: &[u8;16]) -> Option<[u8;16]> {
+    let mut inverse = [0;16];
+    let mut seen = [false;16];
+    for (i, &t) in table.iter().enumerate() {
+        if seen[t as usize] {
+            return None;
+        }
+        seen[t as usize] = true;
+        inverse[t as usize] = i as u8;
+    }
+    Some(inverse)
+}
+
+impl BlockRule {
+    pub fn new(name: &str, even: [u8;16], odd: [u8;16]) -> Result<Self, String> {
+        if let Some(t) = even.iter().chain(odd.iter()).find(|&&t| t >= 16) {
+            return Err(format!("block {} is out of range, entries must be less than 16", t));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            even,
+            odd,
+        })
+    }
+
+    /// A rule that uses the same table in both phases.
+    pub fn uniform(name: &str, table: [u8;16]) -> Result<Self, String> {
+        Self::new(name, table, table)
+    }
+
+    pub fn single_rotation() -> Self {
+        Self::uniform("Single Rotation", SINGLE_ROTATION).unwrap()
+    }
+
+    pub fn critters() -> Self {
+        Self::new("Critters", CRITTERS_EVEN, CRITTERS_ODD).unwrap()
+    }
+
+    pub fn tron() -> Self {
+        Self::uniform("Tron", TRON).unwrap()
+    }
+
+    pub fn billiard_ball() -> Self {
+        Self::uniform("Billiard Ball Machine", BILLIARD_BALL).unwrap()
+    }
+
+    /// One of the built in rules by its short name.
+    pub fn named(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "tron" => Some(Self::tron()),
+            "bbm" => Some(Self::billiard_ball()),
+            "critters" => Some(Self::critters()),
+            "rotation" => Some(Self::single_rotation()),
+            _ => None,
+        }
+    }
+
+    /// A rule is reversible when both tables are permutations of the blocks.
+    pub fn is_reversible(&self) -> bool {
+        self.inverse().is_some()
+    }
+
+    /// The rule that undoes this one, phase by phase.
+    pub fn inverse(&self) -> Option<Self> {
+        Some(Self {
+            name: format!("{} (reversed)", self.name),
+            even: invert_table(&self.even)?,
+            odd: invert_table(&self.odd)?,
+        })
+    }
+
+    /// The help of the built in rule with the same tables, or the general
+    /// Margolus help.
+    pub fn help(&self) -> &'static Help {
+        match (self.even, self.odd) {
+            (CRITTERS_EVEN, CRITTERS_ODD) => &CRITTERS_HELP,
+            (SINGLE_ROTATION, SINGLE_ROTATION) => &ROTATOR_HELP,
+            _ => &HELP,
+        }
+    }
+
+    fn table(&self, odd: bool) -> &[u8;16] {
+        match odd {
+            true => &self.odd,
+            false => &self.even,
+        }
+    }
+}
+
+pub fn run_margolus(rule: BlockRule, options: &Options) -> Result<(), Error> {
+    println!("{} uses blocks\neven: {:?}\nodd:  {:?}", rule.name, rule.even, rule.odd);
+    match rule.is_reversible() {
+        true => println!("This rule is reversible, press V to run it backwards."),
+        false => println!("This rule is not reversible."),
+    }
+    let title = rule.name.clone();
+    let life = MarGrid::new_empty(options.width, options.height, rule, options.boundary);
+    launch(life, &title, options)
+}
+
+const INITIAL_FILL: f32 = 0.95;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Cell {
+    alive: bool,
+}
+
+impl Cell {
+    #[must_use]
+    fn next_state(mut self, alive: bool) -> Self {
+        self.alive = alive;
+        self
+    }
+
+    fn set_alive(&mut self, alive: bool) {
+        *self = self.next_state(alive);
+    }
+
+}
+
+#[derive(Clone, Debug)]
+struct MarGrid {
+    cells: Vec<Cell>,
+    width: usize,
+    height: usize,
+    rule: BlockRule,
+    inverse: Option<BlockRule>,
+    reverse: bool,
+    // false when the next forward step uses the aligned blocks
+    phase: bool,
+    boundary: Boundary,
+}
+
+// Blocks are 2 by 2, so a grid is rounded down to an even number of rows or
+// columns, keeping at least one block
+fn even(n: usize) -> usize {
+    (n & !1).max(2)
+}
+
+impl MarGrid {
+    fn new_empty(width: usize, height: usize, rule: BlockRule, boundary: Boundary) -> Self {
+        assert!(width != 0 && height != 0);
+        let (width, height) = (even(width), even(height));
+        let size = width.checked_mul(height).expect("too big");
+        Self {
+            cells: vec![Cell::default(); size],
+            width,
+            height,
+            inverse: rule.inverse(),
+            rule,
+            reverse: false,
+            phase: false,
+            boundary,
+        }
+    }
+
+    fn reverse(&mut self) {
+        if self.inverse.is_none() {
+            println!("{} is not reversible", self.rule.name);
+            return
+        }
+        self.reverse = !self.reverse;
+        match self.reverse {
+            true => println!("Reverse"),
+            false => println!("Forward"),
+        }
+    }
+
+    // Cells in clockwise order starting from (x, y)
+    fn big_cell(&self, x: usize, y: usize) -> [Neighbor;4] {
+        let (x, y) = (x as isize, y as isize);
+        [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)]
+            .map(|(x, y)| self.boundary.locate(x, y, self.width, self.height))
+    }
+
+    // Only the cells of the block on the grid itself are changed, the parts
+    // hanging over a border or reflected back by a mirror are left alone. This
+    // means only the torus, Klein bottle and cylinder keep reversible rules
+    // reversible all the way to the edge.
+    fn update_big_cell(&mut self, table: &[u8;16], cells: [Neighbor;4]) {
+        let block = cells
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (bit, &p)| acc | (p.is_alive(|i| self.cells[i].alive) as usize) << bit);
+        let next = table[block];
+        for (bit, &p) in cells.iter().enumerate() {
+            if let Neighbor::Cell(i) = p {
+                self.cells[i].set_alive(next >> bit & 1 == 1);
+            }
+        }
+    }
+
+    // Apply a table to every block, shifted by one cell in the odd phase
+    fn update_grid(&mut self, table: [u8;16], odd: bool) {
+        let offset = odd as usize;
+        for yt in 0..self.height/2 {
+            for xt in 0..self.width/2 {
+                let cell_pos = self.big_cell(xt*2 + offset, yt*2 + offset);
+                self.update_big_cell(&table, cell_pos);
+            }
+        }
+    }
+}
+
+impl Automaton for MarGrid {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    // Running backwards undoes the most recent step, so the phase is stepped
+    // back before the inverse table is applied
+    fn update(&mut self) {
+        match (self.reverse, &self.inverse) {
+            (true, Some(inverse)) => {
+                self.phase = !self.phase;
+                let table = *inverse.table(self.phase);
+                self.update_grid(table, self.phase);
+            }
+            _ => {
+                let table = *self.rule.table(self.phase);
+                self.update_grid(table, self.phase);
+                self.phase = !self.phase;
+            }
+        }
+    }
+
+    fn draw(&self, screen: &mut [u8]) {
+        debug_assert_eq!(screen.len(), 4 * self.cells.len());
+        for (c, pix) in self.cells.iter().zip(screen.chunks_exact_mut(4)) {
+            let color = if c.alive {
+                [0xff, 0xff, 0xff, 0xff]
+            } else {
+                [0, 0, 0, 0xff]
+            };
+            pix.copy_from_slice(&color);
+        }
+    }
+
+    fn cell(&self, x: usize, y: usize) -> u32 {
+        self.cells[x + y * self.width].alive as u32
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, value: u32) {
+        self.cells[x + y * self.width].set_alive(value != 0);
+    }
+
+    fn random_cell(&self, rng: &mut randomize::PCG32) -> u32 {
+        (randomize::f32_half_open_right(rng.next_u32()) > INITIAL_FILL) as u32
+    }
+
+    fn set_size(&mut self, width: usize, height: usize) {
+        let (width, height) = (even(width), even(height));
+        self.cells = vec![Cell::default(); width * height];
+        self.width = width;
+        self.height = height;
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        let mut state = save_cells(self);
+        state.push(self.phase as u8);
+        state
+    }
+
+    fn load_state(&mut self, mut state: &[u8]) {
+        load_cells(self, &mut state);
+        self.phase = take_bytes(&mut state, 1)[0] == 1;
+    }
+
+    fn clear(&mut self) {
+        for c in self.cells.iter_mut() {
+            *c = Cell::default();
+        }
+    }
+
+    fn population(&self) -> u64 {
+        self.cells.iter().filter(|c| c.alive).count() as u64
+    }
+
+    fn rule_name(&self) -> String {
+        match self.reverse {
+            true => format!("{} (reversed)", self.rule.name),
+            false => self.rule.name.clone(),
+        }
+    }
+
+
+    fn help(&self) -> &'static Help {
+        self.rule.help()
+    }
+
+    fn handle_keys(&mut self, input: &WinitInputHelper) {
+        if input.key_pressed(VirtualKeyCode::V) {
+            self.reverse();
+        }
+        self.boundary.handle_keys(input);
+    }
+}
@@ -3,4 +3,4 @@ pub mod elementary;
 pub mod life;
 pub mod totalistic;
 pub mod outer_totalistic;
-pub mod single_rotation;
\ No newline at end of file
+pub mod margolus;
\ No newline at end of file
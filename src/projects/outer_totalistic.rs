@@ -3,14 +3,14 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
-use log::{debug, error};
-use pixels::{Error, Pixels, SurfaceTexture};
-use winit::event::{Event, VirtualKeyCode};
-use winit::event_loop::{ControlFlow, EventLoop};
+use pixels::Error;
 use winit_input_helper::WinitInputHelper;
 
-use crate::auxiliary::randomizer::generate_seed;
-use crate::auxiliary::window::{create_window, SCREEN_WIDTH, SCREEN_HEIGHT};
+use crate::auxiliary::automaton::{Automaton, Help};
+use crate::auxiliary::boundary::Boundary;
+use crate::auxiliary::options::Options;
+use crate::auxiliary::rulestring::{parse_rule, rule_to_string};
+use crate::auxiliary::runner::launch;
 
 
 // Nine-square binary outer totalistic rule using Wolfram's method
@@ -21,7 +21,7 @@ fn code_to_rule(mut n: u32) -> ([bool;9],[bool;9]) {
     let mut dead = [false;9];
     for p in 0..18 {
         let b = n%2;
-        n = n/2;
+        n /= 2;
         if b == 1 {
             if p % 2 == 0{
                 live[p/2] = true
@@ -33,125 +33,30 @@ fn code_to_rule(mut n: u32) -> ([bool;9],[bool;9]) {
     (live,dead)
 }
 
-pub fn run_outer_totalistic(n: u32) -> Result<(), Error> {
-    env_logger::init();
-    let event_loop = EventLoop::new();
-    let mut input = WinitInputHelper::new();
-    let (window, p_width, p_height, mut _hidpi_factor) =
-        create_window(
-            "Outer Totalistic Automata", 
-            &event_loop);
-    
-    let surface_texture = SurfaceTexture::new(p_width, p_height, &window);
+// Inverse of code_to_rule
+pub fn rule_to_code(live: [bool;9], dead: [bool;9]) -> u32 {
+    let mut n = 0;
+    for p in (0..18).rev() {
+        let b = if p % 2 == 0 {
+            live[p/2]
+        } else {
+            dead[p/2]
+        };
+        n = 2*n + b as u32;
+    }
+    n
+}
 
-    let (live_rule,dead_rule) = code_to_rule(n);
-    println!("Rule {} parsed as:\n{:?}\n{:?}",n,live_rule,dead_rule);
-    let mut life = Grid::new_empty(SCREEN_WIDTH as usize, SCREEN_HEIGHT as usize, live_rule, dead_rule);
-    let mut pixels = Pixels::new(SCREEN_WIDTH, SCREEN_HEIGHT, surface_texture)?;
-    let mut paused = false;
-
-    let mut draw_state: Option<bool> = None;
-
-
-    event_loop.run(move |event, _, control_flow| {
-        // The one and only event that winit_input_helper doesn't have for us...
-        if let Event::RedrawRequested(_) = event {
-            life.draw(pixels.get_frame());
-            if pixels
-                .render()
-                .map_err(|e| error!("pixels.render() failed: {}", e))
-                .is_err()
-            {
-                *control_flow = ControlFlow::Exit;
-                return;
-            }
-        }
+pub const HELP: Help = Help {
+    description: "These 'Binary Outer Totalistic Automata' count the number of live cells in a nine cell neighborhood to determine the next state. However the rule is different depending on whether the center cell is active.",
+    keys: &[("B", "change boundary")],
+};
 
-        // For everything else, for let winit_input_helper collect events to build its state.
-        // It returns `true` when it is time to update our game state and request a redraw.
-        if input.update(&event) {
-            // Close events
-            if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
-                *control_flow = ControlFlow::Exit;
-                return;
-            }
-            if input.key_pressed(VirtualKeyCode::P) {
-                paused = !paused;
-            }
-            if input.key_pressed(VirtualKeyCode::Space) {
-                // Space is frame-step, so ensure we're paused
-                paused = true;
-            }
-            if input.key_pressed(VirtualKeyCode::R) {
-                life.randomize();
-            }
-            if input.key_pressed(VirtualKeyCode::C) {
-                life.clear();
-            }
-            // Handle mouse. This is a bit involved since support some simple
-            // line drawing (mostly because it makes nice looking patterns).
-            let (mouse_cell, mouse_prev_cell) = input
-                .mouse()
-                .map(|(mx, my)| {
-                    let (dx, dy) = input.mouse_diff();
-                    let prev_x = mx - dx;
-                    let prev_y = my - dy;
-
-                    let (mx_i, my_i) = pixels
-                        .window_pos_to_pixel((mx, my))
-                        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-
-                    let (px_i, py_i) = pixels
-                        .window_pos_to_pixel((prev_x, prev_y))
-                        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-
-                    (
-                        (mx_i as isize, my_i as isize),
-                        (px_i as isize, py_i as isize),
-                    )
-                })
-                .unwrap_or_default();
-
-            if input.mouse_pressed(0) {
-                debug!("Mouse click at {:?}", mouse_cell);
-                draw_state = Some(life.toggle(mouse_cell.0, mouse_cell.1));
-            } else if let Some(draw_alive) = draw_state {
-                let release = input.mouse_released(0);
-                let held = input.mouse_held(0);
-                debug!("Draw at {:?} => {:?}", mouse_prev_cell, mouse_cell);
-                debug!("Mouse held {:?}, release {:?}", held, release);
-                // If they either released (finishing the drawing) or are still
-                // in the middle of drawing, keep going.
-                if release || held {
-                    debug!("Draw line of {:?}", draw_alive);
-                    life.set_line(
-                        mouse_prev_cell.0,
-                        mouse_prev_cell.1,
-                        mouse_cell.0,
-                        mouse_cell.1,
-                        draw_alive,
-                    );
-                }
-                // If they let go or are otherwise not clicking anymore, stop drawing.
-                if release || !held {
-                    debug!("Draw end");
-                    draw_state = None;
-                }
-            }
-            // Adjust high DPI factor
-            if let Some(factor) = input.scale_factor_changed() {
-                _hidpi_factor = factor;
-            }
-            // Resize the window
-            if let Some(size) = input.window_resized() {
-                pixels.resize_surface(size.width, size.height);
-            }
-            if !paused || input.key_pressed(VirtualKeyCode::Space) {
-                life.update();
-            }
-            window.request_redraw();
-        }
-    });
+pub fn run_outer_totalistic(n: u32, options: &Options) -> Result<(), Error> {
+    let (live_rule,dead_rule) = code_to_rule(n);
+    println!("Rule {} parsed as {}",n,rule_to_string(live_rule,dead_rule));
+    let life = Grid::new_empty(options.width, options.height, live_rule, dead_rule, options.boundary);
+    launch(life, "Outer Totalistic Automata", options)
 }
 
 
@@ -164,10 +69,6 @@ struct Cell {
 }
 
 impl Cell {
-    fn new(alive: bool) -> Self {
-        Self { alive }
-    }
-
     #[must_use]
     fn update_neibs(self, n: usize, live: [bool;9], dead: [bool;9],) -> Self {
         let next_alive = if self.alive {
@@ -198,10 +99,11 @@ struct Grid {
     scratch_cells: Vec<Cell>,
     live: [bool;9],
     dead: [bool;9],
+    boundary: Boundary,
 }
 
 impl Grid {
-    fn new_empty(width: usize, height: usize, live: [bool;9], dead: [bool;9]) -> Self {
+    fn new_empty(width: usize, height: usize, live: [bool;9], dead: [bool;9], boundary: Boundary) -> Self {
         assert!(width != 0 && height != 0);
         let size = width.checked_mul(height).expect("too big");
         Self {
@@ -211,40 +113,19 @@ impl Grid {
             height,
             live,
             dead,
+            boundary,
         }
     }
 
-    fn randomize(&mut self) {
-        let mut rng: randomize::PCG32 = generate_seed().into();
-        for c in self.cells.iter_mut() {
-            let alive = randomize::f32_half_open_right(rng.next_u32()) > INITIAL_FILL;
-            *c = Cell::new(alive);
-        }
+    fn count_neibs(&self, x: usize, y: usize) -> usize {
+        self.boundary
+            .count_neibs(x, y, self.width, self.height, |i| self.cells[i].alive)
     }
+}
 
-    fn count_neibs(&self, x: usize, y: usize) -> usize {
-        let (xm1, xp1) = if x == 0 {
-            (self.width - 1, x + 1)
-        } else if x == self.width - 1 {
-            (x - 1, 0)
-        } else {
-            (x - 1, x + 1)
-        };
-        let (ym1, yp1) = if y == 0 {
-            (self.height - 1, y + 1)
-        } else if y == self.height - 1 {
-            (y - 1, 0)
-        } else {
-            (y - 1, y + 1)
-        };
-        self.cells[xm1 + ym1 * self.width].alive as usize
-            + self.cells[x + ym1 * self.width].alive as usize
-            + self.cells[xp1 + ym1 * self.width].alive as usize
-            + self.cells[xm1 + y * self.width].alive as usize
-            + self.cells[xp1 + y * self.width].alive as usize
-            + self.cells[xm1 + yp1 * self.width].alive as usize
-            + self.cells[x + yp1 * self.width].alive as usize
-            + self.cells[xp1 + yp1 * self.width].alive as usize
+impl Automaton for Grid {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
     }
 
     fn update(&mut self) {
@@ -260,22 +141,6 @@ impl Grid {
         std::mem::swap(&mut self.scratch_cells, &mut self.cells);
     }
 
-    fn toggle(&mut self, x: isize, y: isize) -> bool {
-        if let Some(i) = self.grid_idx(x, y) {
-            let was_alive = self.cells[i].alive;
-            self.cells[i].set_alive(!was_alive);
-            !was_alive
-        } else {
-            false
-        }
-    }
-
-    fn clear(&mut self) {
-        for c in self.cells.iter_mut() {
-            *c = Cell::default();
-        }
-    }
-
     fn draw(&self, screen: &mut [u8]) {
         debug_assert_eq!(screen.len(), 4 * self.cells.len());
         for (c, pix) in self.cells.iter().zip(screen.chunks_exact_mut(4)) {
@@ -288,30 +153,56 @@ impl Grid {
         }
     }
 
-    fn set_line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize, alive: bool) {
-        // probably should do sutherland-hodgeman if this were more serious.
-        // instead just clamp the start pos, and draw until moving towards the
-        // end pos takes us out of bounds.
-        let x0 = x0.max(0).min(self.width as isize);
-        let y0 = y0.max(0).min(self.height as isize);
-        for (x, y) in line_drawing::Bresenham::new((x0, y0), (x1, y1)) {
-            if let Some(i) = self.grid_idx(x, y) {
-                self.cells[i].set_alive(alive);
-            } else {
-                break;
-            }
-        }
+    fn cell(&self, x: usize, y: usize) -> u32 {
+        self.cells[x + y * self.width].alive as u32
     }
 
-    fn grid_idx<I: std::convert::TryInto<usize>>(&self, x: I, y: I) -> Option<usize> {
-        if let (Ok(x), Ok(y)) = (x.try_into(), y.try_into()) {
-            if x < self.width && y < self.height {
-                Some(x + y * self.width)
-            } else {
-                None
-            }
-        } else {
-            None
+    fn set_cell(&mut self, x: usize, y: usize, value: u32) {
+        self.cells[x + y * self.width].set_alive(value != 0);
+    }
+
+    fn random_cell(&self, rng: &mut randomize::PCG32) -> u32 {
+        (randomize::f32_half_open_right(rng.next_u32()) > INITIAL_FILL) as u32
+    }
+
+    fn set_size(&mut self, width: usize, height: usize) {
+        self.cells = vec![Cell::default(); width * height];
+        self.scratch_cells = vec![Cell::default(); width * height];
+        self.width = width;
+        self.height = height;
+    }
+
+
+
+    fn clear(&mut self) {
+        for c in self.cells.iter_mut() {
+            *c = Cell::default();
         }
     }
-}
+
+    fn population(&self) -> u64 {
+        self.cells.iter().filter(|c| c.alive).count() as u64
+    }
+
+    fn rule_name(&self) -> String {
+        rule_to_string(self.live, self.dead)
+    }
+
+    fn rulestring(&self) -> Option<String> {
+        Some(self.rule_name())
+    }
+
+    fn set_rulestring(&mut self, rule: &str) -> Result<(), String> {
+        (self.live, self.dead) = parse_rule(rule)?;
+        Ok(())
+    }
+
+
+    fn help(&self) -> &'static Help {
+        &HELP
+    }
+
+    fn handle_keys(&mut self, input: &WinitInputHelper) {
+        self.boundary.handle_keys(input);
+    }
+}
\ No newline at end of file
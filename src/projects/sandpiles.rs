@@ -1,14 +1,21 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
-use log::{debug, error};
-use pixels::{Error, Pixels, SurfaceTexture};
-use winit::event::{Event, VirtualKeyCode};
-use winit::event_loop::{ControlFlow, EventLoop};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use pixels::Error;
+use winit::event::VirtualKeyCode;
 use winit_input_helper::WinitInputHelper;
 
-use crate::auxiliary::randomizer::generate_seed;
-use crate::auxiliary::window::{create_window, SCREEN_WIDTH, SCREEN_HEIGHT};
+use crate::auxiliary::automaton::{grid_pos, line_cells, load_cells, resize_cells, save_cells, Automaton, Help};
+use crate::auxiliary::boundary::Boundary;
+use crate::auxiliary::history::{put_u64, take_u64};
+use crate::auxiliary::randomizer::seeded_rng;
+use crate::auxiliary::options::Options;
+use crate::auxiliary::runner::launch;
+use crate::auxiliary::screenshot::timestamped_filename;
 
 
 // We are going to create a very simple sandpile dynamical system
@@ -26,131 +33,103 @@ const CENTER_HEIGHT: u32 = 98304;
 // how many grains a clicked pixel is set to
 const CLICK_HEIGHT: u32 = 256;
 
+// how many single grains are dropped per frame while driving the pile
+const DRIVE_GRAINS: usize = 200;
 
 
-pub fn run_piles() -> Result<(), Error> {
-    env_logger::init();
-    let event_loop = EventLoop::new();
-    let mut input = WinitInputHelper::new();
-    let (window, p_width, p_height, mut _hidpi_factor) =
-        create_window(
-            "Sandpiles", 
-            &event_loop);
-    
-    let surface_texture = SurfaceTexture::new(p_width, p_height, &window);
-
-    let mut piles = SandPiles::new_center(SCREEN_WIDTH as usize, SCREEN_HEIGHT as usize);
-    let mut pixels = Pixels::new(SCREEN_WIDTH, SCREEN_HEIGHT, surface_texture)?;
-    let mut paused = false;
-
-    let mut draw_state: Option<bool> = None;
-
-    event_loop.run(move |event, _, control_flow| {
-        // The one and only event that winit_input_helper doesn't have for us...
-        if let Event::RedrawRequested(_) = event {
-            piles.draw(pixels.get_frame());
-            if pixels
-                .render()
-                .map_err(|e| error!("pixels.render() failed: {}", e))
-                .is_err()
-            {
-                *control_flow = ControlFlow::Exit;
-            }
+
+/// What happens to grains toppled over the edge of the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Edge {
+    /// They fall into a sink and are lost, the classic Abelian sandpile
+    Sink,
+    /// They wrap around to the opposite edge
+    Torus,
+    /// They bounce back onto the pile that toppled
+    Reflect,
+}
+
+impl Edge {
+    // The shared --boundary option, for the boundaries that make sense here
+    fn from_boundary(boundary: Boundary) -> Result<Self, String> {
+        match boundary {
+            Boundary::Dead => Ok(Edge::Sink),
+            Boundary::Torus => Ok(Edge::Torus),
+            Boundary::Mirror => Ok(Edge::Reflect),
+            _ => Err(format!(
+                "sandpiles can't use the {} boundary, only dead, torus or mirror",
+                boundary.name()
+            )),
         }
+    }
 
-        // For everything else, for let winit_input_helper collect events to build its state.
-        // It returns `true` when it is time to update our game state and request a redraw.
-        if input.update(&event) {
-            // Close events
-            if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
-                *control_flow = ControlFlow::Exit;
-            }
-            if input.key_pressed(VirtualKeyCode::P) {
-                paused = !paused;
-            }
-            if input.key_pressed(VirtualKeyCode::Space) {
-                // Space is frame-step, so ensure we're paused
-                paused = true;
-            }
-            if input.key_pressed(VirtualKeyCode::R) {
-                piles.clear();
-                piles.randomize();
-            }
-            if input.key_pressed(VirtualKeyCode::N) {
-                piles.clear();
-                piles.center_pile();
-            }
-            if input.key_pressed(VirtualKeyCode::L) {
-                piles.clear();
-                piles.center_line();
-            }
-            if input.key_pressed(VirtualKeyCode::C) {
-                piles.clear();
-            }
-            // Handle mouse. This is a bit involved since support some simple
-            // line drawing (mostly because it makes nice looking patterns).
-            let (mouse_cell, mouse_prev_cell) = input
-                .mouse()
-                .map(|(mx, my)| {
-                    let (dx, dy) = input.mouse_diff();
-                    let prev_x = mx - dx;
-                    let prev_y = my - dy;
-
-                    let (mx_i, my_i) = pixels
-                        .window_pos_to_pixel((mx, my))
-                        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-
-                    let (px_i, py_i) = pixels
-                        .window_pos_to_pixel((prev_x, prev_y))
-                        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-
-                    (
-                        (mx_i as isize, my_i as isize),
-                        (px_i as isize, py_i as isize),
-                    )
-                })
-                .unwrap_or_default();
-
-            if input.mouse_pressed(0) {
-                debug!("Mouse click at {:?}", mouse_cell);
-                draw_state = Some(piles.set_pile(mouse_cell.0, mouse_cell.1));
-            } else if let Some(draw_alive) = draw_state {
-                let release = input.mouse_released(0);
-                let held = input.mouse_held(0);
-                debug!("Draw at {:?} => {:?}", mouse_prev_cell, mouse_cell);
-                debug!("Mouse held {:?}, release {:?}", held, release);
-                // If they either released (finishing the drawing) or are still
-                // in the middle of drawing, keep going.
-                if release || held {
-                    debug!("Draw line of {:?}", draw_alive);
-                    piles.set_line(
-                        mouse_prev_cell.0,
-                        mouse_prev_cell.1,
-                        mouse_cell.0,
-                        mouse_cell.1
-                    );
-                }
-                // If they let go or are otherwise not clicking anymore, stop drawing.
-                if release || !held {
-                    debug!("Draw end");
-                    draw_state = None;
-                }
-            }
+    fn next(self) -> Self {
+        match self {
+            Edge::Sink => Edge::Torus,
+            Edge::Torus => Edge::Reflect,
+            Edge::Reflect => Edge::Sink,
+        }
+    }
 
-            // Adjust high DPI factor
-            if let Some(factor) = input.scale_factor_changed() {
-                _hidpi_factor = factor;
-            }
-            // Resize the window
-            if let Some(size) = input.window_resized() {
-                pixels.resize_surface(size.width, size.height);
-            }
-            if !paused || input.key_pressed(VirtualKeyCode::Space) {
-                piles.update();
-            }
-            window.request_redraw();
+    fn name(self) -> &'static str {
+        match self {
+            Edge::Sink => "sink",
+            Edge::Torus => "torus",
+            Edge::Reflect => "reflecting",
+        }
+    }
+}
+
+/// The configuration the sandpiles start from.
+#[derive(Clone, Copy, Debug)]
+pub enum Preset {
+    CenterPile,
+    CenterLine,
+    /// The identity of the sandpile group for the grid size
+    Identity,
+    /// Three grains everywhere, the largest stable configuration
+    MaxStable,
+}
+
+impl Preset {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "pile" => Ok(Preset::CenterPile),
+            "line" => Ok(Preset::CenterLine),
+            "identity" => Ok(Preset::Identity),
+            "max" => Ok(Preset::MaxStable),
+            _ => Err(format!("unknown preset '{}', expected pile, line, identity or max", name)),
         }
-    });
+    }
+}
+
+pub const HELP: Help = Help {
+    description: "Sandpiles are a very simple 2D cellular automata in which a pile with four or more grains drops one grain into each of its four immediate neighbors. Despite this extremely simple rule Sandpiles create durable patterns and shapes.",
+    keys: &[
+        ("N", "center pile"),
+        ("L", "center line"),
+        ("T", "topple everything until stable"),
+        ("A", "start/stop dropping random grains and recording the avalanches they cause"),
+        ("B", "switch between a sink that loses grains over the edge, a torus and a reflecting edge"),
+        ("I", "identity of the sandpile group"),
+        ("M", "maximal stable configuration"),
+        ("K", "store the grid"),
+        ("J", "add the stored grid to the grid"),
+        ("U", "test whether the grid is recurrent"),
+    ],
+};
+
+/// Whether sandpiles can run with the shared `--boundary` option.
+pub fn check_boundary(boundary: Boundary) -> Result<(), String> {
+    Edge::from_boundary(boundary).map(|_| ())
+}
+
+pub fn run_piles(preset: Preset, options: &Options) -> Result<(), Error> {
+    let boundary = Edge::from_boundary(options.boundary).map_err(|e| Error::UserDefined(e.into()))?;
+    let mut piles = SandPiles::new_empty(options.width, options.height);
+    piles.boundary = boundary;
+    piles.apply_preset(preset);
+    launch(piles, "Sandpiles", options)
 }
 
 
@@ -202,12 +181,111 @@ fn pixel_color(height: u32) -> [u8; 4] {
     }
 }
 
+// Graph laplacian of the grid with the sink all around it, how many grains
+// each pile loses when every pile x topples amounts[x] times
+fn laplacian(amounts: &[f64], out: &mut [f64], width: usize, height: usize) {
+    for y in 0..height {
+        for x in 0..width {
+            let i = x + y * width;
+            let mut lost = 4.0 * amounts[i];
+            if y > 0 { lost -= amounts[i - width]; }
+            if x > 0 { lost -= amounts[i - 1]; }
+            if x + 1 < width { lost -= amounts[i + 1]; }
+            if y + 1 < height { lost -= amounts[i + width]; }
+            out[i] = lost;
+        }
+    }
+}
+
+// A lower bound on how many times each pile topples while stabilizing, which
+// can all be done at once before relaxing the rest as usual.
+//
+// If every pile toppled u times and ended with s grains then L u = c - s, and
+// since s is at most 3 the solution of L v = c - 3 is never more than u (L is
+// an M-matrix). v is found with conjugate gradients and the error is bounded
+// by the largest residual times the largest row sum of L^-1, which is at
+// most (min(width, height) + 1)^2 / 8, so rounding down after subtracting
+// that error gives a safe integer bound. Without convergence it gives up with 0.
+fn odometer_bound(heights: &[i64], width: usize, height: usize) -> Vec<i64> {
+    let size = heights.len();
+    let b: Vec<f64> = heights.iter().map(|&h| (h - 3) as f64).collect();
+    let dot = |a: &[f64], b: &[f64]| a.iter().zip(b.iter()).map(|(a, b)| a * b).sum::<f64>();
+    let short = width.min(height) as f64 + 1.0;
+    let max_error = 0.25;
+    let tolerance = max_error / (short * short / 8.0);
+    let mut v = vec![0.0; size];
+    let mut r = b.clone();
+    let mut p = r.clone();
+    let mut lp = vec![0.0; size];
+    let mut rr = dot(&r, &r);
+    for _ in 0..20 * (width + height) {
+        if rr == 0.0 || r.iter().all(|e| e.abs() < tolerance / 2.0) {
+            break;
+        }
+        laplacian(&p, &mut lp, width, height);
+        let alpha = rr / dot(&p, &lp);
+        for i in 0..size {
+            v[i] += alpha * p[i];
+            r[i] -= alpha * lp[i];
+        }
+        let next_rr = dot(&r, &r);
+        for i in 0..size {
+            p[i] = r[i] + next_rr / rr * p[i];
+        }
+        rr = next_rr;
+    }
+    // The recurrence drifts, so check the true residual
+    laplacian(&v, &mut lp, width, height);
+    if lp.iter().zip(b.iter()).any(|(lv, b)| (lv - b).abs() >= tolerance) {
+        return vec![0; size];
+    }
+    v.iter().map(|&t| (t - max_error).max(0.0).floor() as i64).collect()
+}
+
+// Topple the pile at (x, y) t times, grains that go over the edge are lost
+fn topple_open(heights: &mut [i64], width: usize, x: usize, y: usize, t: i64) {
+    let i = x + y * width;
+    heights[i] -= 4 * t;
+    if y > 0 { heights[i - width] += t; }
+    if x > 0 { heights[i - 1] += t; }
+    if x + 1 < width { heights[i + 1] += t; }
+    if i + width < heights.len() { heights[i + width] += t; }
+}
+
+/// Statistics for the avalanche set off by relaxing the piles.
+#[derive(Clone, Copy, Debug, Default)]
+struct Avalanche {
+    // total number of topplings
+    size: u64,
+    // number of waves of toppling before the piles were stable
+    duration: u32,
+    // number of distinct piles that toppled
+    area: usize,
+    // false if the piles can never settle
+    complete: bool,
+}
+
 #[derive(Clone, Debug)]
 struct SandPiles {
     piles: Vec<Pile>,
     width: usize,
     height: usize,
     scratch_piles: Vec<Pile>,
+    boundary: Edge,
+    // grains that have fallen into the sink since the grid was last cleared
+    lost: u64,
+    // a configuration remembered to be added to the grid later
+    stored: Option<Vec<Pile>>,
+    // when set each update drops single grains at random and records the
+    // avalanche each one causes
+    driving: bool,
+    avalanches: Vec<Avalanche>,
+    rng: randomize::PCG32,
+    // scratch space for stabilize, `queued` marks piles waiting to topple and
+    // `toppled` holds the id of the last avalanche each pile took part in
+    queued: Vec<bool>,
+    toppled: Vec<u32>,
+    avalanche_id: u32,
 }
 
 impl SandPiles {
@@ -219,70 +297,345 @@ impl SandPiles {
             scratch_piles: vec![Pile::default(); size],
             width,
             height,
+            boundary: Edge::Torus,
+            lost: 0,
+            stored: None,
+            driving: false,
+            avalanches: Vec::new(),
+            rng: seeded_rng(0),
+            queued: vec![false; size],
+            toppled: vec![0; size],
+            avalanche_id: 0,
         }
     }
 
-    fn new_center(width: usize, height: usize) -> Self {
-        let mut result = Self::new_empty(width, height);
-        result.center_pile();
-        result
+    fn apply_preset(&mut self, preset: Preset) {
+        self.clear();
+        match preset {
+            Preset::CenterPile => self.center_pile(),
+            Preset::CenterLine => self.center_line(),
+            Preset::Identity => self.identity(),
+            Preset::MaxStable => self.max_stable(),
+        }
     }
 
     fn center_pile(&mut self) {
-        let pos = self.grid_idx(SCREEN_WIDTH/2, SCREEN_HEIGHT/2).unwrap();
-        self.piles[pos].set_grains_inplace(CENTER_HEIGHT);
+        self.set_cell(self.width/2, self.height/2, CENTER_HEIGHT);
     }
 
     fn center_line(&mut self) {
-        let y = SCREEN_HEIGHT/2;
-        for x in 0..SCREEN_WIDTH {
-            if x > 40 && x < SCREEN_WIDTH-40 {
-                let pos = self.grid_idx(x, y).unwrap();
-                self.piles[pos].set_grains_inplace(512);
+        // leave a ninth of the width empty at each end
+        let margin = self.width/9;
+        let y = self.height/2;
+        for x in 0..self.width {
+            if x > margin && x + margin < self.width {
+                self.set_cell(x, y, 512);
             }
         }
     }
 
-    fn randomize(&mut self) {
-        let mut rng: randomize::PCG32 = generate_seed().into();
-        for c in self.piles.iter_mut() {
-            let alive = randomize::f32_half_open_right(rng.next_u32()) < RANDOM_FILL;
-            if alive {
-                let grains = rng.next_u32() % 64;
-                *c = Pile::new(grains);
+    fn max_stable(&mut self) {
+        for p in self.piles.iter_mut() {
+            p.set_grains_inplace(TOPPLE_HEIGHT - 1);
+        }
+    }
+
+    fn set_boundary(&mut self, boundary: Edge) {
+        self.boundary = boundary;
+        println!("{} boundary, {} grains lost so far", boundary.name(), self.lost);
+    }
+
+    // The sandpile group is only defined when grains can be lost
+    fn use_sink(&mut self) {
+        if self.boundary != Edge::Sink {
+            self.set_boundary(Edge::Sink);
+        }
+    }
+
+    /// Stabilize with grains lost over the edge, returning the number of
+    /// topplings. Much faster than `stabilize` when most piles topple many
+    /// times, as when every pile starts out unstable.
+    fn settle(&mut self) -> u64 {
+        debug_assert_eq!(self.boundary, Edge::Sink);
+        let mut heights: Vec<i64> = self.piles.iter().map(|p| p.grains as i64).collect();
+        let before: i64 = heights.iter().sum();
+        let bound = odometer_bound(&heights, self.width, self.height);
+        let mut topplings = 0;
+        for (i, &t) in bound.iter().enumerate() {
+            topple_open(&mut heights, self.width, i % self.width, i / self.width, t);
+            topplings += t as u64;
+        }
+        // Some piles may have gone negative but that sorts itself out
+        let mut stable = false;
+        while !stable {
+            stable = true;
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let t = heights[x + y * self.width] / TOPPLE_HEIGHT as i64;
+                    if t > 0 {
+                        topple_open(&mut heights, self.width, x, y, t);
+                        topplings += t as u64;
+                        stable = false;
+                    }
+                }
             }
         }
+        for (p, &h) in self.piles.iter_mut().zip(heights.iter()) {
+            debug_assert!(h >= 0);
+            p.set_grains_inplace(h as u32);
+        }
+        self.lost += (before - heights.iter().sum::<i64>()) as u64;
+        topplings
     }
 
-    fn clear(&mut self) {
-        for c in self.piles.iter_mut() {
-            *c = Pile::default();
+    /// Replace the grid with the identity of the sandpile group, computed as
+    /// stab(2m - stab(2m)) where m is the maximal stable configuration.
+    fn identity(&mut self) {
+        self.use_sink();
+        println!("computing the identity, this can take a while on big grids");
+        let twice_max = Pile::new(2 * (TOPPLE_HEIGHT - 1));
+        for p in self.piles.iter_mut() {
+            *p = twice_max;
+        }
+        self.settle();
+        for p in self.piles.iter_mut() {
+            p.set_grains_inplace(twice_max.grains - p.grains);
         }
+        self.settle();
     }
 
-    // Each neighbor tall enough to topple contributes a single grain
-    fn count_tall_neibs(&self, x: usize, y: usize) -> u32 {
-        let (xm1, xp1) = if x == 0 {
-            (self.width - 1, x + 1)
-        } else if x == self.width - 1 {
-            (x - 1, 0)
-        } else {
-            (x - 1, x + 1)
+    /// Add a configuration to the grid pile by pile and stabilize the sum.
+    fn add_config(&mut self, other: &[Pile]) -> u64 {
+        debug_assert_eq!(other.len(), self.piles.len());
+        self.use_sink();
+        for (p, o) in self.piles.iter_mut().zip(other.iter()) {
+            *p = p.add_grains(o.grains);
+        }
+        self.settle()
+    }
+
+    fn is_stable(&self) -> bool {
+        self.piles.iter().all(|p| p.grains < TOPPLE_HEIGHT)
+    }
+
+    /// Whether the grid is a recurrent configuration, an element of the
+    /// sandpile group. Uses Dhar's burning test: adding one grain for every
+    /// edge joining a pile to the sink makes each pile topple exactly once
+    /// and gives back the same configuration only if it is recurrent.
+    fn is_recurrent(&self) -> bool {
+        if !self.is_stable() {
+            return false;
+        }
+        let mut burnt = self.clone();
+        burnt.boundary = Edge::Sink;
+        burnt.driving = false;
+        let burning: Vec<Pile> = (0..self.piles.len())
+            .map(|i| Pile::new(burnt.neibs(i).iter().filter(|n| n.is_none()).count() as u32))
+            .collect();
+        burnt.add_config(&burning);
+        burnt.piles.iter().zip(self.piles.iter()).all(|(a, b)| a.grains == b.grains)
+    }
+
+    // The four piles that receive grains when the pile at idx topples, None
+    // where the grain falls into the sink
+    fn neibs(&self, idx: usize) -> [Option<usize>;4] {
+        let (x, y) = (idx % self.width, idx / self.width);
+        let wrap = |i: usize, len: usize, step: isize| -> Option<usize> {
+            let j = i as isize + step;
+            if j >= 0 && j < len as isize {
+                return Some(j as usize);
+            }
+            match self.boundary {
+                Edge::Sink => None,
+                Edge::Torus => Some(j.rem_euclid(len as isize) as usize),
+                Edge::Reflect => Some(i),
+            }
         };
-        let (ym1, yp1) = if y == 0 {
-            (self.height - 1, y + 1)
-        } else if y == self.height - 1 {
-            (y - 1, 0)
+        [wrap(y, self.height, -1).map(|y| x + y * self.width),
+         wrap(x, self.width, -1).map(|x| x + y * self.width),
+         wrap(x, self.width, 1).map(|x| x + y * self.width),
+         wrap(y, self.height, 1).map(|y| x + y * self.width)]
+    }
+
+    /// Topple every pile until the whole grid is stable.
+    fn stabilize(&mut self) -> Avalanche {
+        let unstable = (0..self.piles.len())
+            .filter(|&i| self.piles[i].grains >= TOPPLE_HEIGHT)
+            .collect();
+        self.relax(unstable)
+    }
+
+    // Queue based relaxation. Each wave topples every unstable pile as many
+    // times as it can at once and the neighbors pushed over the edge make up
+    // the next wave. Since the sandpile is abelian the order doesn't change
+    // the final configuration. Without a sink no grains are lost, and once every
+    // pile has toppled the relaxation can never finish (Bjorner, Lovasz and
+    // Shor), so it stops there.
+    fn relax(&mut self, mut wave: Vec<usize>) -> Avalanche {
+        self.avalanche_id = self.avalanche_id.wrapping_add(1);
+        let mut stats = Avalanche { complete: true, ..Avalanche::default() };
+        for &i in wave.iter() {
+            self.queued[i] = true;
+        }
+        let mut next = Vec::new();
+        while !wave.is_empty() {
+            if self.boundary != Edge::Sink && stats.area == self.piles.len() {
+                for &i in wave.iter() {
+                    self.queued[i] = false;
+                }
+                stats.complete = false;
+                break;
+            }
+            stats.duration += 1;
+            for &i in wave.iter() {
+                self.queued[i] = false;
+                let topples = self.piles[i].grains / TOPPLE_HEIGHT;
+                if topples == 0 {
+                    continue;
+                }
+                self.piles[i].grains -= topples * TOPPLE_HEIGHT;
+                stats.size += topples as u64;
+                if self.toppled[i] != self.avalanche_id {
+                    self.toppled[i] = self.avalanche_id;
+                    stats.area += 1;
+                }
+                for n in self.neibs(i) {
+                    let Some(n) = n else {
+                        self.lost += topples as u64;
+                        continue;
+                    };
+                    self.piles[n] = self.piles[n].add_grains(topples);
+                    if self.piles[n].grains >= TOPPLE_HEIGHT && !self.queued[n] {
+                        self.queued[n] = true;
+                        next.push(n);
+                    }
+                }
+            }
+            std::mem::swap(&mut wave, &mut next);
+            next.clear();
+        }
+        stats
+    }
+
+    /// Drop a single grain on the pile at idx and relax the grid.
+    fn add_grain(&mut self, idx: usize) -> Avalanche {
+        self.piles[idx] = self.piles[idx].add_grains(1);
+        if self.piles[idx].grains >= TOPPLE_HEIGHT {
+            self.relax(vec![idx])
         } else {
-            (y - 1, y + 1)
-        };
-        self.piles[x + ym1 * self.width].give_grain()
-            + self.piles[xm1 + y * self.width].give_grain()
-            + self.piles[xp1 + y * self.width].give_grain()
-            + self.piles[x + yp1 * self.width].give_grain()
+            Avalanche { complete: true, ..Avalanche::default() }
+        }
+    }
+
+    fn drive(&mut self) {
+        for _ in 0..DRIVE_GRAINS {
+            let idx = self.rng.next_u32() as usize % self.piles.len();
+            let avalanche = self.add_grain(idx);
+            self.avalanches.push(avalanche);
+            if !avalanche.complete {
+                println!("the grid is too full to settle");
+                self.toggle_driving();
+                return;
+            }
+        }
+    }
+
+    fn toggle_driving(&mut self) {
+        self.driving = !self.driving;
+        if self.driving {
+            // Start from a stable grid so each avalanche is caused by one grain
+            if !self.stabilize().complete {
+                println!("the grid is too full to settle");
+                self.driving = false;
+                return;
+            }
+            self.avalanches.clear();
+            println!("dropping grains and recording avalanches");
+        } else {
+            self.report_avalanches();
+        }
+    }
+
+    // Summarize the recorded avalanches with a histogram of sizes in powers of
+    // two, which should look like a straight line on a log-log plot near the
+    // critical state, and save every avalanche to a CSV file.
+    fn report_avalanches(&self) {
+        let count = self.avalanches.len();
+        println!("{} grains dropped", count);
+        if count == 0 {
+            return;
+        }
+        let mut bins = [0usize; 65];
+        for a in self.avalanches.iter() {
+            let bin = if a.size == 0 { 0 } else { 64 - a.size.leading_zeros() as usize };
+            bins[bin] += 1;
+        }
+        println!("{:>12} {:>10}", "size", "count");
+        for (bin, &n) in bins.iter().enumerate().filter(|(_, &n)| n > 0) {
+            let range = match bin {
+                0 => "0".to_string(),
+                1 => "1".to_string(),
+                _ => format!("{}-{}", 1u64 << (bin - 1), (1u128 << bin) - 1),
+            };
+            println!("{:>12} {:>10}", range, n);
+        }
+        let total: u64 = self.avalanches.iter().map(|a| a.size).sum();
+        let longest = self.avalanches.iter().map(|a| a.duration).max().unwrap_or(0);
+        let widest = self.avalanches.iter().map(|a| a.area).max().unwrap_or(0);
+        println!("mean size {:.2}, longest {} waves, widest {} piles", total as f64 / count as f64, longest, widest);
+        let path = timestamped_filename("avalanches", "csv");
+        match self.write_avalanches(&path) {
+            Ok(()) => println!("avalanches saved to {}", path.display()),
+            Err(e) => println!("could not save avalanches: {}", e),
+        }
+    }
+
+    fn write_avalanches(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "size,duration,area")?;
+        for a in self.avalanches.iter() {
+            writeln!(out, "{},{},{}", a.size, a.duration, a.area)?;
+        }
+        out.flush()
+    }
+
+    // Grains the piles along the edge drop into the sink in one update
+    fn edge_loss(&self) -> u64 {
+        let (w, h) = (self.width, self.height);
+        let top_bottom = (0..w).flat_map(|x| [x, x + (h - 1) * w]);
+        let sides = (1..h.saturating_sub(1)).flat_map(|y| [y * w, w - 1 + y * w]);
+        let mut edge: Vec<usize> = top_bottom.chain(sides).collect();
+        // a one pile wide grid would count its piles twice
+        edge.sort_unstable();
+        edge.dedup();
+        edge.into_iter()
+            .map(|i| {
+                let off_edge = self.neibs(i).iter().filter(|n| n.is_none()).count() as u64;
+                self.piles[i].give_grain() as u64 * off_edge
+            })
+            .sum()
+    }
+
+    // Each neighbor tall enough to topple contributes a single grain
+    fn count_tall_neibs(&self, x: usize, y: usize) -> u32 {
+        self.neibs(x + y * self.width)
+            .into_iter()
+            .flatten()
+            .map(|n| self.piles[n].give_grain())
+            .sum()
+    }
+}
+
+impl Automaton for SandPiles {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
     }
 
     fn update(&mut self) {
+        if self.driving {
+            self.drive();
+            return;
+        }
         for y in 0..self.height {
             for x in 0..self.width {
                 let neibs = self.count_tall_neibs(x, y);
@@ -292,6 +645,9 @@ impl SandPiles {
                 self.scratch_piles[idx] = next;
             }
         }
+        if self.boundary == Edge::Sink {
+            self.lost += self.edge_loss();
+        }
         // We've been writing to a the temporary scratch_piles
         // Now that we're done just swap the memory
         std::mem::swap(&mut self.scratch_piles, &mut self.piles);
@@ -305,37 +661,261 @@ impl SandPiles {
         }
     }
 
-    fn set_pile(&mut self, x: isize, y: isize) -> bool {
-        if let Some(i) = self.grid_idx(x, y) {
-            self.piles[i].set_grains_inplace(CLICK_HEIGHT);
+    // Clicking always sets a pile rather than toggling it
+    fn toggle(&mut self, x: isize, y: isize) -> bool {
+        if let Some((x, y)) = grid_pos(x, y, self.size()) {
+            self.set_cell(x, y, CLICK_HEIGHT);
         }
         true
     }
 
-    fn set_line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize) {
-        // probably should do sutherland-hodgeman if this were more serious.
-        // instead just clamp the start pos, and draw until moving towards the
-        // end pos takes us out of bounds.
-        let x0 = x0.max(0).min(self.width as isize);
-        let y0 = y0.max(0).min(self.height as isize);
-        for (x, y) in line_drawing::Bresenham::new((x0, y0), (x1, y1)) {
-            if let Some(i) = self.grid_idx(x, y) {
-                self.piles[i].set_grains_inplace(CLICK_HEIGHT);
-            } else {
-                break;
+    fn set_line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize, _alive: bool) {
+        for (x, y) in line_cells((x0, y0), (x1, y1), self.size()) {
+            self.set_cell(x, y, CLICK_HEIGHT);
+        }
+    }
+
+    fn cell(&self, x: usize, y: usize) -> u32 {
+        self.piles[x + y * self.width].grains
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, value: u32) {
+        self.piles[x + y * self.width].set_grains_inplace(value);
+    }
+
+    // The tallest stable pile
+    fn fill_value(&self) -> u32 {
+        TOPPLE_HEIGHT - 1
+    }
+
+    fn random_cell(&self, rng: &mut randomize::PCG32) -> u32 {
+        if randomize::f32_half_open_right(rng.next_u32()) < RANDOM_FILL {
+            rng.next_u32() % 64
+        } else {
+            0
+        }
+    }
+
+    fn set_size(&mut self, width: usize, height: usize) {
+        self.piles = vec![Pile::default(); width * height];
+        self.scratch_piles = vec![Pile::default(); width * height];
+        self.queued = vec![false; width * height];
+        self.toppled = vec![0; width * height];
+        self.width = width;
+        self.height = height;
+    }
+    fn resize(&mut self, width: usize, height: usize) {
+        // The stored grid and the avalanche shading are resized along with
+        // the piles
+        let (old_width, old_height) = (self.width, self.height);
+        let piles = resize_cells(&self.piles, old_width, old_height, width, height, Pile::default());
+        let toppled = resize_cells(&self.toppled, old_width, old_height, width, height, 0);
+        self.stored = self
+            .stored
+            .take()
+            .map(|stored| resize_cells(&stored, old_width, old_height, width, height, Pile::default()));
+        self.set_size(width, height);
+        self.piles = piles;
+        self.toppled = toppled;
+    }
+
+    // Each byte of the grain counts is saved separately, since the high ones
+    // are nearly always zero
+    fn save_state(&self) -> Vec<u8> {
+        let mut state = save_cells(self);
+        put_u64(&mut state, self.lost);
+        state
+    }
+
+    fn load_state(&mut self, mut state: &[u8]) {
+        load_cells(self, &mut state);
+        self.lost = take_u64(&mut state);
+    }
+
+    fn clear(&mut self) {
+        for c in self.piles.iter_mut() {
+            *c = Pile::default();
+        }
+        self.lost = 0;
+    }
+
+    fn population(&self) -> u64 {
+        self.piles.iter().map(|p| p.grains as u64).sum()
+    }
+
+    fn population_name(&self) -> &'static str {
+        "grains"
+    }
+
+    fn rule_name(&self) -> String {
+        format!("{} boundary", self.boundary.name())
+    }
+
+    fn randomize(&mut self, seed: u64) {
+        self.clear();
+        let mut rng = seeded_rng(seed);
+        for i in 0..self.piles.len() {
+            let grains = self.random_cell(&mut rng);
+            self.piles[i] = Pile::new(grains);
+        }
+        // Grains dropped while driving follow on from the same seed
+        self.rng = rng;
+    }
+
+    fn has_preset(&self) -> bool {
+        true
+    }
+
+    fn help(&self) -> &'static Help {
+        &HELP
+    }
+
+    fn handle_keys(&mut self, input: &WinitInputHelper) {
+        if input.key_pressed(VirtualKeyCode::N) {
+            self.apply_preset(Preset::CenterPile);
+        }
+        if input.key_pressed(VirtualKeyCode::L) {
+            self.apply_preset(Preset::CenterLine);
+        }
+        if input.key_pressed(VirtualKeyCode::I) {
+            self.apply_preset(Preset::Identity);
+        }
+        if input.key_pressed(VirtualKeyCode::M) {
+            self.apply_preset(Preset::MaxStable);
+        }
+        if input.key_pressed(VirtualKeyCode::K) {
+            self.stored = Some(self.piles.clone());
+            println!("configuration stored");
+        }
+        if input.key_pressed(VirtualKeyCode::J) {
+            match self.stored.take() {
+                Some(stored) => {
+                    let topplings = self.add_config(&stored);
+                    println!("added the stored configuration, {} topplings", topplings);
+                    self.stored = Some(stored);
+                }
+                None => println!("press K to store a configuration first"),
+            }
+        }
+        if input.key_pressed(VirtualKeyCode::U) {
+            match (self.is_stable(), self.is_recurrent()) {
+                (false, _) => println!("not stable, press T to stabilize"),
+                (true, true) => println!("recurrent"),
+                (true, false) => println!("not recurrent"),
             }
         }
+        if input.key_pressed(VirtualKeyCode::T) {
+            let avalanche = self.stabilize();
+            println!(
+                "stabilized after {} topplings in {} waves covering {} piles, {} grains lost",
+                avalanche.size, avalanche.duration, avalanche.area, self.lost
+            );
+            if !avalanche.complete {
+                println!("every pile toppled, there are too many grains to ever settle");
+            }
+        }
+        if input.key_pressed(VirtualKeyCode::A) {
+            self.toggle_driving();
+        }
+        if input.key_pressed(VirtualKeyCode::B) {
+            self.set_boundary(self.boundary.next());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sink_grid(width: usize, height: usize) -> SandPiles {
+        let mut piles = SandPiles::new_empty(width, height);
+        piles.boundary = Edge::Sink;
+        piles
+    }
+
+    fn grains(piles: &SandPiles) -> Vec<u32> {
+        piles.piles.iter().map(|p| p.grains).collect()
+    }
+
+    // Settle a copy and stabilize the other the slow way
+    fn assert_settles_like_stabilize(piles: &SandPiles) {
+        let mut settled = piles.clone();
+        let mut stabilized = piles.clone();
+        let topplings = settled.settle();
+        let avalanche = stabilized.stabilize();
+        assert_eq!(grains(&settled), grains(&stabilized));
+        assert_eq!(topplings, avalanche.size);
+        assert_eq!(settled.lost, stabilized.lost);
+        assert!(settled.is_stable());
     }
 
-    fn grid_idx<I: std::convert::TryInto<usize>>(&self, x: I, y: I) -> Option<usize> {
-        if let (Ok(x), Ok(y)) = (x.try_into(), y.try_into()) {
-            if x < self.width && y < self.height {
-                Some(x + y * self.width)
-            } else {
-                None
+    #[test]
+    fn settle_matches_stabilize_on_random_grids() {
+        for (seed, (width, height)) in [(1, (7, 5)), (2, (16, 16)), (3, (1, 9)), (4, (23, 11))] {
+            let mut piles = sink_grid(width, height);
+            let mut rng = seeded_rng(seed);
+            for p in piles.piles.iter_mut() {
+                *p = Pile::new(rng.next_u32() % 40);
             }
-        } else {
-            None
+            assert_settles_like_stabilize(&piles);
+        }
+    }
+
+    #[test]
+    fn settle_matches_stabilize_on_a_huge_center_pile() {
+        let mut piles = sink_grid(31, 25);
+        piles.set_cell(15, 12, 100_000);
+        assert_settles_like_stabilize(&piles);
+    }
+
+    #[test]
+    fn identity_is_recurrent_and_neutral() {
+        for (width, height) in [(1, 1), (6, 4), (15, 15)] {
+            let mut identity = sink_grid(width, height);
+            identity.identity();
+            assert!(identity.is_stable());
+            assert!(identity.is_recurrent());
+
+            let mut max = sink_grid(width, height);
+            max.max_stable();
+            let expected = grains(&max);
+            max.add_config(&identity.piles);
+            assert_eq!(grains(&max), expected);
+
+            let mut twice = identity.clone();
+            twice.add_config(&identity.piles);
+            assert_eq!(grains(&twice), grains(&identity));
         }
     }
+
+    #[test]
+    fn saved_state_restores_size_grains_and_lost() {
+        let mut piles = sink_grid(9, 7);
+        piles.set_cell(4, 3, 70_000);
+        piles.set_cell(0, 6, 3);
+        piles.lost = 12;
+        let state = piles.save_state();
+
+        let mut restored = sink_grid(4, 4);
+        restored.load_state(&state);
+        assert_eq!(restored.size(), (9, 7));
+        assert_eq!(grains(&restored), grains(&piles));
+        assert_eq!(restored.lost, 12);
+    }
+
+    #[test]
+    fn resize_keeps_the_shared_corner() {
+        let mut piles = sink_grid(5, 4);
+        piles.set_cell(1, 1, 7);
+        piles.set_cell(4, 3, 9);
+        piles.resize(3, 6);
+        assert_eq!(piles.size(), (3, 6));
+        assert_eq!(piles.cell(1, 1), 7);
+        assert_eq!(grains(&piles).iter().sum::<u32>(), 7);
+    }
+
+    #[test]
+    fn empty_grid_is_not_recurrent() {
+        assert!(!sink_grid(5, 5).is_recurrent());
+    }
 }